@@ -0,0 +1,43 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::{decode, Instruction};
+
+/// Decodes every unprefixed and prefixed opcode.
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+
+    group.bench_function("try_from", |b| b.iter(|| {
+        for opcode in 0..=0xFFu8 {
+            black_box(Instruction::try_from((black_box(opcode), 0x00)).ok());
+            black_box(Instruction::try_from((0xCB, black_box(opcode))).ok());
+        }
+    }));
+
+    group.bench_function("table", |b| b.iter(|| {
+        for opcode in 0..=0xFFu8 {
+            black_box(decode(black_box(opcode), 0x00));
+            black_box(decode(0xCB, black_box(opcode)));
+        }
+    }));
+
+    group.finish();
+}
+
+fn bench_run(c: &mut Criterion) {
+    let cartridge = Cartridge::new("rom/blargg/06-ld r,r.gb");
+
+    c.bench_function("run 06-ld r,r", |b| b.iter(|| {
+        let mut cpu = cpu::init_cpu();
+        let mut mmu = Mmu::new(Cartridge { content: cartridge.content.clone() });
+        cpu.run(&mut mmu);
+        black_box(cpu.cycles())
+    }));
+}
+
+criterion_group!(benches, bench_decode, bench_run);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+//! Emulation throughput: instructions per second on the blargg ROMs, time per instruction
+//! for every opcode, and frames per second.
+
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::cpu::{Cpu, StepResult, CYCLES_PER_FRAME};
+use ruboy::lcd;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::{decode, InstructionType};
+use ruboy::opcodes::RegisterId::{B, C, D, E, H, L};
+
+const ROMS: [&str; 4] = [
+    "rom/blargg/01-special.gb",
+    "rom/blargg/06-ld r,r.gb",
+    "rom/blargg/08-misc instrs.gb",
+    "rom/blargg/10-bit ops.gb",
+];
+
+fn load(path: &str) -> Mmu {
+    Mmu::new(Cartridge::new(path))
+}
+
+/// Runs a ROM to completion and returns the number of instructions executed.
+fn run(cpu: &mut Cpu, mmu: &mut Mmu) -> u64 {
+    let mut instructions = 1;
+    while cpu.step(mmu) == StepResult::Continue {
+        instructions += 1;
+    }
+    instructions
+}
+
+fn bench_roms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("instructions");
+    group.sample_size(10);
+
+    for path in ROMS {
+        let instructions = run(&mut cpu::init_cpu(), &mut load(path));
+        group.throughput(Throughput::Elements(instructions));
+
+        let name = path.rsplit('/').next().unwrap();
+        group.bench_function(name, |b| b.iter_batched(
+            || load(path),
+            |mut mmu| run(&mut cpu::init_cpu(), &mut mmu),
+            criterion::BatchSize::LargeInput,
+        ));
+    }
+
+    group.finish();
+}
+
+/// Executes each opcode on its own, with memory operands pointing to work RAM.
+fn bench_opcodes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("opcodes");
+    group.sample_size(10)
+        .warm_up_time(Duration::from_millis(20))
+        .measurement_time(Duration::from_millis(100));
+
+    let opcodes = (0..=0xFFu8).map(|opcode| (opcode, 0x00))
+        .filter(|&(opcode, _)| opcode != 0xCB)
+        .chain((0..=0xFFu8).map(|opcode| (0xCB, opcode)));
+
+    for (opcode, cb_opcode) in opcodes {
+        let instr = match decode(opcode, cb_opcode) {
+            // HALT and STOP leave the CPU waiting, without executing anything else
+            Some(instr) if !matches!(instr.kind, InstructionType::HALT | InstructionType::STOP) => instr,
+            _ => continue,
+        };
+
+        let mut mmu = Mmu::new(Cartridge { content: vec![0; 0x8000] });
+        mmu[0x0100] = opcode;
+        mmu[0x0101] = cb_opcode;
+
+        let mut cpu = cpu::init_cpu();
+        for (high, low) in [(B, C), (D, E), (H, L)] {
+            cpu.regs[high] = 0xC0;
+            cpu.regs[low] = 0x00;
+        }
+
+        let id = if opcode == 0xCB { format!("CB {:02X}", cb_opcode) } else { format!("{:02X}", opcode) };
+        group.bench_function(BenchmarkId::new(id, instr.mnemonic), |b| b.iter(|| {
+            cpu.regs.pc = 0x0100;
+            cpu.regs.sp = 0xDFF0;
+            black_box(cpu.step(&mut mmu))
+        }));
+    }
+
+    group.finish();
+}
+
+/// Emulates and renders frames of a ROM that keeps drawing to the screen.
+fn bench_frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frames");
+    group.throughput(Throughput::Elements(1));
+
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = load("rom/blargg/cpu_instrs.gb");
+
+    group.bench_function("cpu_instrs", |b| b.iter(|| {
+        let end = cpu.cycles() + CYCLES_PER_FRAME;
+        while cpu.cycles() < end {
+            if cpu.step(&mut mmu) != StepResult::Continue {
+                // Start over once the ROM is done
+                cpu = cpu::init_cpu();
+                mmu = load("rom/blargg/cpu_instrs.gb");
+                break;
+            }
+        }
+        black_box(lcd::render(&mmu))
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_roms, bench_opcodes, bench_frames);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ruboy::opcodes::{self, Instruction};
+
+fuzz_target!(|data: &[u8]| {
+    let [opcode, cb_opcode, immediate, ..] = *data else {
+        return;
+    };
+
+    let decoded = Instruction::try_from((opcode, cb_opcode));
+    let cached = opcodes::decode(opcode, cb_opcode);
+    assert_eq!(decoded.is_ok(), cached.is_some());
+
+    let Ok(instr) = decoded else {
+        return;
+    };
+    assert_eq!(cached.unwrap().mnemonic, instr.mnemonic);
+
+    // The byte after the opcode only matters behind the 0xCB prefix
+    assert_eq!(opcode == 0xCB, instr.is_prefixed());
+    if opcode != 0xCB {
+        assert_eq!(instr.mnemonic, Instruction::try_from((opcode, !cb_opcode)).unwrap().mnemonic);
+    }
+
+    assert!((1..=3).contains(&instr.length()), "{}", instr.mnemonic);
+    assert_eq!(0, instr.cycles % 4, "{}", instr.mnemonic);
+    assert!(instr.cycles_taken() >= instr.cycles, "{}", instr.mnemonic);
+
+    let bytes = [opcode, cb_opcode, immediate];
+    let length = instr.length() as usize;
+    assert!(opcodes::disassemble(&bytes[..length]).is_some(), "{}", instr.mnemonic);
+    assert!(opcodes::disassemble(&bytes[..length - 1]).is_none(), "{}", instr.mnemonic);
+});
@@ -0,0 +1,71 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ruboy::bus::{Bus, FlatBus};
+use ruboy::cpu::{self, IllegalOpcodePolicy, StepResult};
+use ruboy::opcodes;
+
+const MAX_STEPS: usize = 10_000;
+
+/// POP AF
+const POP_AF: u8 = 0xF1;
+
+// The first two bytes are the address the rest is loaded and run from, so that programs can
+// wrap around 0xFFFF.
+fuzz_target!(|data: &[u8]| {
+    let [low, high, ref program @ ..] = *data else {
+        return;
+    };
+    let start = u16::from_le_bytes([low, high]);
+
+    let mut bus = FlatBus::new();
+    for (offset, &byte) in program.iter().take(0x10000).enumerate() {
+        bus.poke(start.wrapping_add(offset as u16), byte);
+    }
+
+    let mut cpu = cpu::init_cpu();
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+    let mut state = cpu.state();
+    state.pc = start;
+    cpu.set_state(state);
+
+    for _ in 0..MAX_STEPS {
+        let before = cpu.state();
+        let opcode = bus.peek(before.pc);
+        let instr = opcodes::decode(opcode, bus.peek(before.pc.wrapping_add(1)));
+        let (cpu_cycles, bus_cycles) = (cpu.cycles(), bus.cycles());
+
+        let result = cpu.step(&mut bus);
+        let after = cpu.state();
+
+        assert_eq!(0, after.f & 0x0F);
+        // The rest of the machine doesn't run in STOP mode
+        if !before.stopped {
+            assert_eq!(cpu.cycles() - cpu_cycles, bus.cycles() - bus_cycles);
+        }
+
+        match result {
+            StepResult::Continue => {}
+            // Infinite JR -2 loop
+            StepResult::Stopped => return,
+            StepResult::IllegalOpcode { pc, opcode } => {
+                assert!(instr.is_none());
+                assert_eq!((before.pc, bus.peek(before.pc)), (pc, opcode));
+                return;
+            }
+            StepResult::Breakpoint => panic!("No breakpoint opcode was set"),
+        }
+
+        if before.halted || before.stopped {
+            continue;
+        }
+
+        let instr = instr.unwrap();
+        if !instr.is_branch() {
+            assert_eq!(before.pc.wrapping_add(instr.length() as u16), after.pc, "{}", instr.mnemonic);
+        }
+        if opcode == POP_AF {
+            assert_eq!(bus.peek(before.sp) & 0xF0, after.f);
+        }
+    }
+});
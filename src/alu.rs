@@ -0,0 +1,79 @@
+//! Arithmetic core of the CPU. Operations return their result along with the flags they
+//! compute, leaving it to the caller to store whichever flags the instruction affects.
+
+/// Result of an 8-bit operation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Alu8 {
+    pub value: u8,
+    pub z: bool,
+    pub n: bool,
+    pub h: bool,
+    pub c: bool,
+}
+
+/// Result of a 16-bit addition. Z and N are not computed, since no 16-bit addition sets Z
+/// and all of them reset N.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Alu16 {
+    pub value: u16,
+    pub h: bool,
+    pub c: bool,
+}
+
+/// `a + b + carry`, as done by ADD and ADC.
+pub fn add8(a: u8, b: u8, carry: bool) -> Alu8 {
+    let sum = a as u16 + b as u16 + carry as u16;
+    let value = sum as u8;
+
+    Alu8 {
+        value,
+        z: value == 0,
+        n: false,
+        h: (a & 0x0F) + (b & 0x0F) + carry as u8 > 0x0F,
+        c: sum > 0xFF,
+    }
+}
+
+/// `a - b - carry`, as done by SUB, SBC and CP.
+pub fn sub8(a: u8, b: u8, carry: bool) -> Alu8 {
+    let value = a.wrapping_sub(b).wrapping_sub(carry as u8);
+
+    Alu8 {
+        value,
+        z: value == 0,
+        n: true,
+        h: (a & 0x0F) < (b & 0x0F) + carry as u8,
+        c: (a as u16) < b as u16 + carry as u16,
+    }
+}
+
+/// INC leaves the carry flag untouched, so `c` is always false.
+pub fn inc8(a: u8) -> Alu8 {
+    Alu8 { c: false, ..add8(a, 1, false) }
+}
+
+/// DEC leaves the carry flag untouched, so `c` is always false.
+pub fn dec8(a: u8) -> Alu8 {
+    Alu8 { c: false, ..sub8(a, 1, false) }
+}
+
+/// `ADD HL,rr`: the flags are the carries out of bits 11 and 15.
+pub fn add16(a: u16, b: u16) -> Alu16 {
+    let (value, c) = a.overflowing_add(b);
+
+    Alu16 {
+        value,
+        h: (a & 0x0FFF) + (b & 0x0FFF) > 0x0FFF,
+        c,
+    }
+}
+
+/// `SP + r8`, as done by `ADD SP,r8` and `LD HL,SP+r8`: the offset is signed, but the flags
+/// are the carries out of bits 3 and 7 of an unsigned addition of the low byte.
+pub fn add_sp(sp: u16, offset: u8) -> Alu16 {
+    Alu16 {
+        value: sp.wrapping_add(offset as i8 as u16),
+        h: (sp & 0x000F) + (offset as u16 & 0x000F) > 0x000F,
+        c: (sp & 0x00FF) + offset as u16 > 0x00FF,
+    }
+}
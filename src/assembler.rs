@@ -0,0 +1,725 @@
+//! SM83 assembler, matching source lines against the mnemonics of `opcodes`.
+//!
+//! The syntax follows the mnemonics of the decoding table, case-insensitively:
+//!
+//! ```text
+//! SECTION "main", $0150     ; following code is assembled from $0150
+//! start:
+//!     ld a, $F0             ; numbers are $hex, 0xhex, %binary, 0bbinary or decimal
+//!     ldh ($80), a          ; LDH also takes the full address, $FF80
+//! .loop:
+//!     dec a
+//!     jr nz, .loop          ; relative jumps take the target address...
+//!     jr -2                 ; ...or an explicit offset, as printed by the disassembler
+//!     jp start + 3
+//! table:
+//!     db 1, 2, "text"
+//!     dw start, table
+//! ```
+//!
+//! Labels starting with a dot are local to the last label without one: `.loop` above is
+//! `start.loop`, and can be referred to by that name from elsewhere. Code before the first
+//! section starts at `DEFAULT_ORIGIN`. ALU instructions are accepted with and without the
+//! `A,` destination (`SUB B` and `SUB A,B`).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::bus::Bus;
+use crate::cartridge::Cartridge;
+use crate::opcodes::{self, Instruction, InstructionType};
+
+/// Address of the code written before any `SECTION`: the entry point of cartridges, where
+/// `tests/common::build_cartridge` puts programs.
+pub const DEFAULT_ORIGIN: u16 = 0x0100;
+
+/// Names which can't be labels, as they would be mistaken for operands
+const RESERVED: [&str; 15] = ["A", "B", "C", "D", "E", "H", "L", "AF", "BC", "DE", "HL", "SP", "NZ", "Z", "NC"];
+
+const ROM_SIZE: usize = 0x8000;
+
+/// Assembles the given source lines, panicking on errors. Evaluates to the bytes of the
+/// program, see `Program::bytes`.
+///
+/// ```
+/// use ruboy::asm;
+///
+/// let program = asm!(
+///     "ld a, $F0",
+///     "loop: jr loop",
+/// );
+/// assert_eq!(vec![0x3E, 0xF0, 0x18, 0xFE], program);
+/// ```
+#[macro_export]
+macro_rules! asm {
+    ($($line:expr),* $(,)?) => {
+        match $crate::assembler::assemble(&[$(AsRef::<str>::as_ref(&$line)),*].join("\n")) {
+            Ok(program) => program.bytes(),
+            Err(e) => panic!("{}", e),
+        }
+    };
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    sections: Vec<Section>,
+    labels: HashMap<String, u16>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub address: u16,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    /// Line of the source, starting from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl Program {
+    /// Sections in the order they appear in the source.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Address of a label. Local labels are named after their scope, like `start.loop`.
+    pub fn label(&self, name: &str) -> Option<u16> {
+        self.labels.get(name).copied()
+    }
+
+    /// Memory image from the start of the lowest section to the end of the highest one, with
+    /// zeros between sections.
+    pub fn bytes(&self) -> Vec<u8> {
+        let Some(start) = self.sections.iter().map(|s| s.address as usize).min() else {
+            return Vec::new();
+        };
+        let end = self.sections.iter().map(Section::end).max().unwrap();
+
+        let mut bytes = vec![0; end - start];
+        for section in &self.sections {
+            let offset = section.address as usize - start;
+            bytes[offset..offset + section.bytes.len()].copy_from_slice(&section.bytes);
+        }
+        bytes
+    }
+
+    /// 32 KiB ROM holding the sections. Panics if a section doesn't fit in ROM.
+    pub fn cartridge(&self) -> Cartridge {
+        let mut content = vec![0; ROM_SIZE];
+
+        for section in &self.sections {
+            assert!(section.end() <= ROM_SIZE, "Section \"{}\" ends outside of ROM", section.name);
+            let start = section.address as usize;
+            content[start..section.end()].copy_from_slice(&section.bytes);
+        }
+
+        Cartridge { content }
+    }
+
+    /// Writes the sections to memory, without side effects.
+    pub fn load(&self, bus: &mut impl Bus) {
+        for section in &self.sections {
+            for (address, &byte) in (section.address..).zip(&section.bytes) {
+                bus.poke(address, byte);
+            }
+        }
+    }
+}
+
+impl Section {
+    fn end(&self) -> usize {
+        self.address as usize + self.bytes.len()
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Program, AsmError> {
+    let mut assembler = Assembler::default();
+
+    for (index, line) in source.lines().enumerate() {
+        assembler.line = index + 1;
+        assembler.parse_line(line).map_err(|message| assembler.error(message))?;
+    }
+
+    assembler.check_overlaps()?;
+
+    let Assembler { mut sections, labels, statements, .. } = assembler;
+    for statement in statements {
+        let bytes = statement.encode(&labels)
+            .map_err(|message| AsmError { line: statement.line, message })?;
+        sections[statement.section].section.bytes.extend(bytes);
+    }
+
+    Ok(Program {
+        sections: sections.into_iter().map(|s| s.section).collect(),
+        labels,
+    })
+}
+
+/// First pass: statements are parsed and given an address, labels are collected. Their
+/// operands are only evaluated afterwards, once all labels are known.
+#[derive(Default)]
+struct Assembler {
+    sections: Vec<SectionState>,
+    /// Index of the section being assembled
+    current: Option<usize>,
+    labels: HashMap<String, u16>,
+    /// Last label not starting with a dot, which local labels belong to
+    scope: String,
+    statements: Vec<Statement>,
+    line: usize,
+}
+
+struct SectionState {
+    /// Bytes are only filled in by the second pass
+    section: Section,
+    len: usize,
+    line: usize,
+}
+
+struct Statement {
+    section: usize,
+    address: u16,
+    line: usize,
+    kind: StatementKind,
+}
+
+enum StatementKind {
+    Instruction { instr: &'static Instruction, opcode: Vec<u8>, immediate: Option<(Immediate, Expr)> },
+    Bytes(Vec<Data>),
+    Words(Vec<Expr>),
+}
+
+enum Data {
+    Byte(Expr),
+    Text(Vec<u8>),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Immediate {
+    /// d8
+    Byte,
+    /// a8, the low byte of an address in 0xFF00-0xFFFF
+    HighPage,
+    /// d16 and a16
+    Word,
+    /// r8 of relative jumps
+    Relative,
+    /// r8 added to SP
+    Signed,
+}
+
+impl Assembler {
+    fn error(&self, message: String) -> AsmError {
+        AsmError { line: self.line, message }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut line = strip_comment(line).trim();
+
+        if let Some((label, rest)) = line.split_once(':') {
+            if is_identifier(label.trim()) {
+                self.define_label(label.trim())?;
+                line = rest.trim();
+            }
+        }
+
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        let (word, operands) = match line.split_once(char::is_whitespace) {
+            Some((word, operands)) => (word, operands.trim()),
+            None => (line, ""),
+        };
+        let word = word.to_ascii_uppercase();
+        let operands = split_operands(operands)?;
+
+        match word.as_str() {
+            "SECTION" => self.start_section(&operands),
+            "DB" => {
+                let data = operands.iter().map(|operand| parse_data(operand)).collect::<Result<_, _>>()?;
+                self.push(StatementKind::Bytes(data))
+            }
+            "DW" => {
+                let words = operands.iter().map(|operand| parse_expr(operand)).collect::<Result<_, _>>()?;
+                self.push(StatementKind::Words(words))
+            }
+            _ => {
+                let kind = match_instruction(&word, &operands)?;
+                self.push(kind)
+            }
+        }
+    }
+
+    fn define_label(&mut self, name: &str) -> Result<(), String> {
+        if RESERVED.contains(&name.to_ascii_uppercase().as_str()) {
+            return Err(format!("'{}' is a register or condition, not a label", name));
+        }
+        let address = self.address()?;
+
+        let name = if name.starts_with('.') {
+            format!("{}{}", self.scope, name)
+        } else {
+            self.scope = name.to_owned();
+            name.to_owned()
+        };
+
+        if self.labels.contains_key(&name) {
+            return Err(format!("Label '{}' is already defined", name));
+        }
+        self.labels.insert(name, address);
+        Ok(())
+    }
+
+    fn start_section(&mut self, operands: &[String]) -> Result<(), String> {
+        let [name, address] = operands else {
+            return Err("Expected SECTION \"name\", address".to_owned());
+        };
+        let Some(Data::Text(name)) = parse_data(name).ok() else {
+            return Err(format!("Expected a section name in quotes, got '{}'", name));
+        };
+        let name = String::from_utf8(name).unwrap();
+        let address = parse_expr(address)?.constant()
+            .ok_or_else(|| "Section addresses can't refer to labels".to_owned())?;
+        let address = u16::try_from(address)
+            .map_err(|_| format!("Section address {} is out of range", address))?;
+
+        if self.sections.iter().any(|s| s.section.name == name) {
+            return Err(format!("Section \"{}\" is already defined", name));
+        }
+        self.open_section(name, address);
+        Ok(())
+    }
+
+    fn open_section(&mut self, name: String, address: u16) {
+        self.sections.push(SectionState {
+            section: Section { name, address, bytes: Vec::new() },
+            len: 0,
+            line: self.line,
+        });
+        self.current = Some(self.sections.len() - 1);
+    }
+
+    /// Address of the next statement, in the default section if none was started.
+    fn address(&mut self) -> Result<u16, String> {
+        if self.current.is_none() {
+            self.open_section(String::new(), DEFAULT_ORIGIN);
+        }
+
+        let state = &self.sections[self.current.unwrap()];
+        u16::try_from(state.section.address as usize + state.len)
+            .map_err(|_| format!("Section \"{}\" goes past $FFFF", state.section.name))
+    }
+
+    fn push(&mut self, mut kind: StatementKind) -> Result<(), String> {
+        let address = self.address()?;
+        kind.qualify_labels(&self.scope);
+        let section = self.current.unwrap();
+
+        let statement = Statement { section, address, line: self.line, kind };
+        let state = &mut self.sections[section];
+        state.len += statement.len();
+        if state.section.address as usize + state.len > 0x10000 {
+            return Err(format!("Section \"{}\" goes past $FFFF", state.section.name));
+        }
+
+        self.statements.push(statement);
+        Ok(())
+    }
+
+    fn check_overlaps(&self) -> Result<(), AsmError> {
+        let mut sections: Vec<&SectionState> = self.sections.iter().collect();
+        sections.sort_by_key(|s| s.section.address);
+
+        for pair in sections.windows(2) {
+            if pair[0].section.address as usize + pair[0].len > pair[1].section.address as usize {
+                let (first, second) = if pair[0].line < pair[1].line { (pair[0], pair[1]) } else { (pair[1], pair[0]) };
+                return Err(AsmError {
+                    line: second.line,
+                    message: format!("Section \"{}\" overlaps section \"{}\"", second.section.name, first.section.name),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StatementKind {
+    /// Names local labels after the scope they're referred to from.
+    fn qualify_labels(&mut self, scope: &str) {
+        let exprs: Vec<&mut Expr> = match self {
+            StatementKind::Instruction { immediate, .. } => immediate.iter_mut().map(|(_, expr)| expr).collect(),
+            StatementKind::Bytes(data) => data.iter_mut()
+                .filter_map(|d| match d {
+                    Data::Byte(expr) => Some(expr),
+                    Data::Text(_) => None,
+                })
+                .collect(),
+            StatementKind::Words(words) => words.iter_mut().collect(),
+        };
+
+        for expr in exprs {
+            for (_, term) in &mut expr.terms {
+                if let Term::Label(name) = term {
+                    if name.starts_with('.') {
+                        name.insert_str(0, scope);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Statement {
+    fn len(&self) -> usize {
+        match &self.kind {
+            StatementKind::Instruction { instr, .. } => instr.length() as usize,
+            StatementKind::Bytes(data) => data.iter()
+                .map(|d| match d {
+                    Data::Byte(_) => 1,
+                    Data::Text(text) => text.len(),
+                })
+                .sum(),
+            StatementKind::Words(words) => 2 * words.len(),
+        }
+    }
+
+    fn encode(&self, labels: &HashMap<String, u16>) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::with_capacity(self.len());
+
+        match &self.kind {
+            StatementKind::Instruction { instr, opcode, immediate } => {
+                bytes.extend(opcode);
+
+                match immediate {
+                    Some((kind, expr)) => {
+                        let next = self.address as i64 + instr.length() as i64;
+                        bytes.extend(encode_immediate(*kind, expr, labels, next)?);
+                    }
+                    // STOP is followed by a padding byte
+                    None if instr.kind == InstructionType::STOP => bytes.push(0x00),
+                    None => {}
+                }
+            }
+            StatementKind::Bytes(data) => {
+                for d in data {
+                    match d {
+                        Data::Byte(expr) => bytes.push(check_range(expr.evaluate(labels)?, -0x80, 0xFF)? as u8),
+                        Data::Text(text) => bytes.extend(text),
+                    }
+                }
+            }
+            StatementKind::Words(words) => {
+                for expr in words {
+                    let word = check_range(expr.evaluate(labels)?, -0x8000, 0xFFFF)? as u16;
+                    bytes.extend(word.to_le_bytes());
+                }
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Encodes an immediate operand. `next` is the address of the following instruction, which
+/// relative jumps are based on.
+fn encode_immediate(kind: Immediate, expr: &Expr, labels: &HashMap<String, u16>, next: i64) -> Result<Vec<u8>, String> {
+    let value = expr.evaluate(labels)?;
+
+    let bytes = match kind {
+        Immediate::Byte => vec![check_range(value, -0x80, 0xFF)? as u8],
+        Immediate::HighPage => match value {
+            0x00..=0xFF | 0xFF00..=0xFFFF => vec![value as u8],
+            _ => return Err(format!("Address ${:04X} isn't in $FF00-$FFFF", value)),
+        },
+        Immediate::Word => (check_range(value, -0x8000, 0xFFFF)? as u16).to_le_bytes().to_vec(),
+        Immediate::Relative => {
+            let offset = if expr.signed { value } else { value - next };
+            vec![check_range(offset, -0x80, 0x7F).map_err(|_| format!("Jump offset {} is out of range", offset))? as u8]
+        }
+        Immediate::Signed => vec![check_range(value, -0x80, 0x7F)? as u8],
+    };
+
+    Ok(bytes)
+}
+
+fn check_range(value: i64, min: i64, max: i64) -> Result<i64, String> {
+    if (min..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("Value {} is out of range", value))
+    }
+}
+
+/// Opcodes with their mnemonic split into operands, grouped by instruction name
+struct Entry {
+    instr: &'static Instruction,
+    opcode: Vec<u8>,
+    operands: Vec<&'static str>,
+}
+
+static ENTRIES: OnceLock<HashMap<&'static str, Vec<Entry>>> = OnceLock::new();
+
+fn entries() -> &'static HashMap<&'static str, Vec<Entry>> {
+    ENTRIES.get_or_init(|| {
+        let unprefixed = (0..=0xFFu8).filter(|&op| op != 0xCB).map(|op| vec![op]);
+        let prefixed = (0..=0xFFu8).map(|op| vec![0xCB, op]);
+
+        let mut entries: HashMap<&'static str, Vec<Entry>> = HashMap::new();
+        for opcode in unprefixed.chain(prefixed) {
+            let Some(instr) = opcodes::decode(opcode[0], *opcode.last().unwrap()) else {
+                continue;
+            };
+            let (name, operands) = match instr.mnemonic.split_once(' ') {
+                Some((name, operands)) => (name, operands.split(',').collect()),
+                None => (instr.mnemonic, Vec::new()),
+            };
+            entries.entry(name).or_default().push(Entry { instr, opcode, operands });
+        }
+        entries
+    })
+}
+
+fn match_instruction(name: &str, operands: &[String]) -> Result<StatementKind, String> {
+    let entries = entries().get(name).ok_or_else(|| format!("Unknown instruction '{}'", name))?;
+
+    // The table isn't consistent about the A destination of ALU instructions
+    let mut candidates = vec![operands.to_vec()];
+    if matches!(name, "ADD" | "ADC" | "SUB" | "SBC" | "AND" | "XOR" | "OR" | "CP") {
+        if operands.len() == 1 {
+            candidates.push(vec!["A".to_owned(), operands[0].clone()]);
+        } else if operands.len() == 2 && operands[0].eq_ignore_ascii_case("A") {
+            candidates.push(vec![operands[1].clone()]);
+        }
+    }
+
+    for operands in &candidates {
+        for entry in entries.iter().filter(|e| e.operands.len() == operands.len()) {
+            if let Some(immediate) = match_operands(entry, operands) {
+                return Ok(StatementKind::Instruction {
+                    instr: entry.instr,
+                    opcode: entry.opcode.clone(),
+                    immediate,
+                });
+            }
+        }
+    }
+
+    Err(format!("Invalid operands for {}: '{}'", name, operands.join(", ")))
+}
+
+/// Matches the operands of the source against the ones of a mnemonic, and returns the
+/// immediate operand if there's one.
+fn match_operands(entry: &Entry, operands: &[String]) -> Option<Option<(Immediate, Expr)>> {
+    let mut immediate = None;
+
+    for (pattern, operand) in entry.operands.iter().zip(operands) {
+        if let Some(expr) = match_operand(pattern, operand)? {
+            immediate = Some((immediate_kind(entry.instr, pattern), expr));
+        }
+    }
+
+    Some(immediate)
+}
+
+/// Returns `None` if the operand doesn't match, and its expression if the pattern has an
+/// immediate placeholder.
+fn match_operand(pattern: &str, operand: &str) -> Option<Option<Expr>> {
+    let upper = operand.to_ascii_uppercase();
+
+    for placeholder in ["d16", "a16", "d8", "a8", "r8"] {
+        if let Some((prefix, suffix)) = pattern.split_once(placeholder) {
+            // SP+r8 also matches SP-2, the sign is part of the offset
+            let (prefix, signed) = match prefix.strip_suffix('+') {
+                Some(prefix) => (prefix, true),
+                None => (prefix, false),
+            };
+            if operand.len() < prefix.len() + suffix.len() || !upper.starts_with(prefix) || !upper.ends_with(suffix) {
+                return None;
+            }
+
+            let expr = parse_expr(&operand[prefix.len()..operand.len() - suffix.len()]).ok()?;
+            if signed && !expr.signed {
+                return None;
+            }
+            return Some(Some(expr));
+        }
+    }
+
+    // Bit numbers and RST vectors can also be written as expressions
+    let value = parse_expr(operand).ok().and_then(|expr| expr.constant());
+    if upper == pattern || (value.is_some() && value == pattern_number(pattern)) {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+fn immediate_kind(instr: &Instruction, pattern: &str) -> Immediate {
+    if pattern.contains("16") {
+        Immediate::Word
+    } else if pattern.contains("a8") {
+        Immediate::HighPage
+    } else if pattern.contains("r8") {
+        if instr.kind == InstructionType::JR { Immediate::Relative } else { Immediate::Signed }
+    } else {
+        Immediate::Byte
+    }
+}
+
+/// Value of numeric operands of the table, like `7` or `38H`.
+fn pattern_number(pattern: &str) -> Option<i64> {
+    match pattern.strip_suffix('H') {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => pattern.parse().ok(),
+    }
+}
+
+/// Sum of numbers and labels
+#[derive(Clone, Debug)]
+struct Expr {
+    terms: Vec<(bool, Term)>,
+    /// Whether the expression starts with a sign, which makes jump operands offsets
+    signed: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Term {
+    Number(i64),
+    Label(String),
+}
+
+impl Expr {
+    fn constant(&self) -> Option<i64> {
+        self.evaluate(&HashMap::new()).ok()
+    }
+
+    fn evaluate(&self, labels: &HashMap<String, u16>) -> Result<i64, String> {
+        let mut value = 0;
+
+        for (negative, term) in &self.terms {
+            let term = match term {
+                Term::Number(n) => *n,
+                Term::Label(name) => *labels.get(name).ok_or_else(|| format!("Unknown label '{}'", name))? as i64,
+            };
+            value += if *negative { -term } else { term };
+        }
+
+        Ok(value)
+    }
+}
+
+fn parse_expr(text: &str) -> Result<Expr, String> {
+    let text = text.trim();
+    let signed = text.starts_with(['+', '-']);
+    let mut terms = Vec::new();
+
+    let mut negative = false;
+    let mut rest = text;
+    if let Some(stripped) = rest.strip_prefix('+') {
+        rest = stripped;
+    } else if let Some(stripped) = rest.strip_prefix('-') {
+        rest = stripped;
+        negative = true;
+    }
+
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        terms.push((negative, parse_term(rest[..end].trim()).ok_or_else(|| format!("Invalid expression '{}'", text))?));
+
+        match rest[end..].chars().next() {
+            Some(sign) => {
+                negative = sign == '-';
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+
+    Ok(Expr { terms, signed })
+}
+
+fn parse_term(text: &str) -> Option<Term> {
+    let number = if let Some(hex) = text.strip_prefix('$').or_else(|| text.strip_prefix("0x")).or_else(|| text.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = text.strip_prefix('%').or_else(|| text.strip_prefix("0b")).or_else(|| text.strip_prefix("0B")) {
+        i64::from_str_radix(binary, 2).ok()
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        text.parse().ok()
+    } else if is_identifier(text) && !RESERVED.contains(&text.to_ascii_uppercase().as_str()) {
+        return Some(Term::Label(text.to_owned()));
+    } else {
+        None
+    };
+
+    number.map(Term::Number)
+}
+
+fn parse_data(text: &str) -> Result<Data, String> {
+    match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        Some(text) => Ok(Data::Text(text.as_bytes().to_vec())),
+        None => parse_expr(text).map(Data::Byte),
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Removes the `;` comment at the end of the line, if it isn't in a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Splits operands on commas outside of strings, and trims them.
+fn split_operands(text: &str) -> Result<Vec<String>, String> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut operands = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+
+    for c in text.chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            ',' if !in_string => operands.push(std::mem::take(&mut current)),
+            c if c.is_whitespace() && !in_string => {}
+            _ => current.push(c),
+        }
+    }
+    if in_string {
+        return Err("Unterminated string".to_owned());
+    }
+    operands.push(current);
+
+    if operands.iter().any(String::is_empty) {
+        return Err(format!("Empty operand in '{}'", text));
+    }
+    Ok(operands)
+}
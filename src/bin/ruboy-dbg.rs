@@ -0,0 +1,284 @@
+//! Interactive command-line debugger.
+//!
+//! Usage: ruboy-dbg <rom> [--gdb <port>]
+//!
+//! With `--gdb`, a GDB remote stub is served on the given local port instead of the REPL.
+
+use std::io::{BufRead, Write};
+use std::net::TcpListener;
+use std::{env, io, process};
+
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::cpu::{Cpu, IllegalOpcodePolicy};
+use ruboy::debugger::{disassemble_at, format_registers, Debugger, StopReason, WatchKind};
+use ruboy::gdb;
+use ruboy::memory::Mmu;
+
+const HELP: &str = "\
+Commands:
+  s, step [n]             execute n instructions (default 1)
+  c, continue             run until a breakpoint, a watchpoint or the end of the program
+  b, break <addr>         set a breakpoint
+  d, delete <addr>        remove a breakpoint
+  w, watch <addr> [r|w|rw] stop when the address is read and/or written (default w)
+  unwatch <addr>          remove a watchpoint
+  i, info                 list breakpoints and watchpoints
+  r, regs                 show registers and flags
+  x <addr> [len]          hexdump memory (default 64 bytes)
+  l, dis [addr] [n]       disassemble n instructions (default: 10 at PC)
+  bt, backtrace           show the call stack
+  q, quit                 exit the debugger
+Addresses and values are hexadecimal, optionally prefixed with $ or 0x.";
+
+struct Session {
+    cpu: Cpu,
+    mmu: Mmu,
+    debugger: Debugger,
+    stopped: bool,
+}
+
+impl Session {
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<(), String> {
+        match command {
+            "s" | "step" => {
+                let count = parse_count(args.first(), 1)?;
+                for _ in 0..count {
+                    self.ensure_running()?;
+                    let reason = self.debugger.step(&mut self.cpu, &mut self.mmu);
+                    if reason != StopReason::Step {
+                        self.report(reason);
+                        break;
+                    }
+                }
+                self.print_current();
+            }
+            "c" | "continue" => {
+                self.ensure_running()?;
+                let reason = self.debugger.resume(&mut self.cpu, &mut self.mmu, None);
+                self.report(reason);
+                self.print_current();
+            }
+            "b" | "break" => {
+                let address = parse_address(args.first())?;
+                self.debugger.add_breakpoint(address);
+                println!("Breakpoint at {:04X}", address);
+            }
+            "d" | "delete" => {
+                let address = parse_address(args.first())?;
+                if !self.debugger.remove_breakpoint(address) {
+                    return Err(format!("No breakpoint at {:04X}", address));
+                }
+            }
+            "w" | "watch" => {
+                let address = parse_address(args.first())?;
+                let kind = match args.get(1).copied() {
+                    None | Some("w") => WatchKind::Write,
+                    Some("r") => WatchKind::Read,
+                    Some("rw") => WatchKind::ReadWrite,
+                    Some(kind) => return Err(format!("Unknown watchpoint kind '{}'", kind)),
+                };
+                self.debugger.add_watchpoint(address, kind);
+                println!("Watchpoint ({:?}) at {:04X}", kind, address);
+            }
+            "unwatch" => {
+                let address = parse_address(args.first())?;
+                if !self.debugger.remove_watchpoint(address) {
+                    return Err(format!("No watchpoint at {:04X}", address));
+                }
+            }
+            "i" | "info" => {
+                for address in self.debugger.breakpoints() {
+                    println!("breakpoint  {:04X}", address);
+                }
+                for watchpoint in self.debugger.watchpoints() {
+                    println!("watchpoint  {:04X} {:?}", watchpoint.address, watchpoint.kind);
+                }
+            }
+            "r" | "regs" => println!("{}", format_registers(&self.cpu)),
+            "x" => {
+                let address = parse_address(args.first())?;
+                let len = parse_count(args.get(1), 64)?;
+                self.hexdump(address, len);
+            }
+            "l" | "dis" => {
+                let address = match args.first() {
+                    Some(_) => parse_address(args.first())?,
+                    None => self.cpu.regs.pc,
+                };
+                let count = parse_count(args.get(1), 10)?;
+                self.disassemble(address, count);
+            }
+            "bt" | "backtrace" => {
+                println!("#0  {:04X}", self.cpu.regs.pc);
+                for (i, frame) in self.debugger.call_stack().iter().rev().enumerate() {
+                    println!("#{}  {:04X}  called from {:04X}, returns to {:04X}",
+                             i + 1, frame.target, frame.call_site, frame.return_address);
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            _ => return Err(format!("Unknown command '{}', try 'help'", command)),
+        }
+
+        Ok(())
+    }
+
+    fn ensure_running(&self) -> Result<(), String> {
+        if self.stopped {
+            Err("The program has stopped".to_owned())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn report(&mut self, reason: StopReason) {
+        match reason {
+            StopReason::Breakpoint(address) => println!("Breakpoint hit at {:04X}", address),
+            StopReason::Watchpoint { address, access, value } => {
+                println!("Watchpoint hit: {:?} of {:04X} (value {:02X})", access, address, value)
+            }
+            StopReason::Stopped => {
+                self.stopped = true;
+                println!("Program stopped");
+            }
+            StopReason::IllegalOpcode { pc, opcode } => println!("Illegal opcode {:02X} at {:04X}", opcode, pc),
+            StopReason::Step | StopReason::Limit => {}
+        }
+    }
+
+    fn print_current(&self) {
+        let (text, _) = disassemble_at(&self.mmu, self.cpu.regs.pc);
+        println!("{}", format_registers(&self.cpu));
+        println!("{:04X}  {}", self.cpu.regs.pc, text);
+    }
+
+    fn hexdump(&self, address: u16, len: usize) {
+        for row in (0..len).step_by(16) {
+            let start = address.wrapping_add(row as u16);
+            let bytes: Vec<u8> = (0..16.min(len - row))
+                .map(|i| self.mmu[start.wrapping_add(i as u16)])
+                .collect();
+            let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            let ascii: String = bytes.iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+
+            println!("{:04X}  {:<47}  {}", start, hex.join(" "), ascii);
+        }
+    }
+
+    fn disassemble(&self, address: u16, count: usize) {
+        let mut address = address;
+
+        for _ in 0..count {
+            let (text, len) = disassemble_at(&self.mmu, address);
+            let marker = if address == self.cpu.regs.pc { "=>" } else { "  " };
+            println!("{} {:04X}  {}", marker, address, text);
+            address = address.wrapping_add(len);
+        }
+    }
+}
+
+fn parse_address(arg: Option<&&str>) -> Result<u16, String> {
+    let arg = arg.ok_or("Missing address")?;
+    let digits = arg.trim_start_matches("0x").trim_start_matches('$');
+
+    u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid address '{}'", arg))
+}
+
+fn parse_count(arg: Option<&&str>, default: usize) -> Result<usize, String> {
+    match arg {
+        Some(arg) => arg.parse().map_err(|_| format!("Invalid number '{}'", arg)),
+        None => Ok(default),
+    }
+}
+
+const USAGE: &str = "Usage: ruboy-dbg <rom> [--gdb <port>]";
+
+fn serve_gdb(port: &str, cpu: &mut Cpu, mmu: &mut Mmu) {
+    let port: u16 = port.parse().unwrap_or_else(|_| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap_or_else(|e| {
+        eprintln!("Couldn't listen on port {}: {}", port, e);
+        process::exit(1);
+    });
+
+    println!("Waiting for gdb on 127.0.0.1:{}", port);
+
+    if let Err(e) = gdb::serve(&listener, cpu, mmu) {
+        eprintln!("gdb session failed: {}", e);
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let path = match args.as_slice() {
+        [path] | [path, _, _] => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let cartridge = Cartridge::load(path).unwrap_or_else(|e| {
+        eprintln!("Couldn't read {}: {}", path, e);
+        process::exit(2);
+    });
+
+    if let [_, option, port] = args.as_slice() {
+        if option != "--gdb" {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+
+        let mut cpu = cpu::init_cpu();
+        cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+        let mut mmu = Mmu::new(cartridge);
+        serve_gdb(port, &mut cpu, &mut mmu);
+        return;
+    }
+
+    let mut session = Session {
+        cpu: cpu::init_cpu(),
+        mmu: Mmu::new(cartridge),
+        debugger: Debugger::new(),
+        stopped: false,
+    };
+    session.cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+
+    session.print_current();
+
+    let stdin = io::stdin();
+    let mut last = String::new();
+
+    loop {
+        print!("(ruboy) ");
+        io::stdout().flush().expect("Couldn't flush stdout");
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("Couldn't read command") == 0 {
+            break;
+        }
+
+        // An empty line repeats the previous command, like gdb
+        let line = if line.trim().is_empty() { last.clone() } else { line.trim().to_owned() };
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.first() {
+            None => continue,
+            Some(&"q") | Some(&"quit") => break,
+            Some(command) => {
+                if let Err(message) = session.execute(command, &words[1..]) {
+                    println!("{}", message);
+                }
+            }
+        }
+
+        last = line;
+    }
+}
@@ -0,0 +1,162 @@
+//! Runs a ROM and compares its execution trace, line by line, against a reference log in
+//! the Gameboy Doctor format. Stops at the first divergence and prints the instructions
+//! that led to it.
+//!
+//! Usage: ruboy-trace-diff <rom> <reference log> [--context <lines>]
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::{env, io, process};
+
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::cpu::Registers;
+use ruboy::memory::Mmu;
+use ruboy::opcodes;
+use ruboy::opcodes::RegisterId::{A, B, C, D, E, H, L};
+use ruboy::trace::Tracer;
+
+const USAGE: &str = "Usage: ruboy-trace-diff <rom> <reference log> [--context <lines>]";
+
+struct TraceDiff {
+    reference: Lines<BufReader<File>>,
+    history: VecDeque<String>,
+    context: usize,
+    line_number: usize,
+}
+
+impl TraceDiff {
+    fn check(&mut self, actual: &str) {
+        self.line_number += 1;
+
+        let expected = match self.reference.next() {
+            Some(line) => line.expect("Couldn't read reference log"),
+            None => {
+                println!("Reference log exhausted after {} matching instructions", self.line_number - 1);
+                exit(0);
+            }
+        };
+
+        if expected.trim_end() != actual {
+            self.report(&expected, actual);
+            exit(1);
+        }
+
+        if self.context > 0 {
+            if self.history.len() == self.context {
+                self.history.pop_front();
+            }
+            self.history.push_back(actual.to_owned());
+        }
+    }
+
+    fn report(&self, expected: &str, actual: &str) {
+        println!("Divergence at instruction {}", self.line_number);
+        println!();
+
+        let first = self.line_number - self.history.len();
+        for (i, line) in self.history.iter().enumerate() {
+            println!("  {:>8}  {}  {}", first + i, line, describe(line));
+        }
+        println!();
+        println!("expected  {}  {}", expected, describe(expected));
+        println!("actual    {}  {}", actual, describe(actual));
+        println!("          {}", mark_differences(expected, actual));
+    }
+}
+
+/// Disassembles the PCMEM bytes at the end of a trace line.
+fn describe(line: &str) -> String {
+    let bytes: Vec<u8> = line.rsplit("PCMEM:")
+        .next()
+        .unwrap_or("")
+        .split(',')
+        .filter_map(|b| u8::from_str_radix(b.trim(), 16).ok())
+        .collect();
+
+    opcodes::disassemble(&bytes).unwrap_or_else(|| "???".to_owned())
+}
+
+fn mark_differences(expected: &str, actual: &str) -> String {
+    expected.chars()
+        .zip(actual.chars())
+        .map(|(e, a)| if e == a { ' ' } else { '^' })
+        .collect::<String>()
+        .trim_end()
+        .to_owned()
+}
+
+fn exit(code: i32) -> ! {
+    io::stdout().flush().expect("Couldn't flush stdout");
+    process::exit(code);
+}
+
+/// Loads the register values left by the DMG boot ROM, which reference logs start from.
+fn load_post_boot_registers(regs: &mut Registers) {
+    regs[A] = 0x01;
+    regs.flags.set_f(0xB0);
+    regs[B] = 0x00;
+    regs[C] = 0x13;
+    regs[D] = 0x00;
+    regs[E] = 0xD8;
+    regs[H] = 0x01;
+    regs[L] = 0x4D;
+    regs.sp = 0xFFFE;
+    regs.pc = 0x0100;
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut paths = Vec::new();
+    let mut context = 10;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--context" => {
+                i += 1;
+                context = args.get(i).and_then(|n| n.parse().ok()).unwrap_or_else(|| {
+                    eprintln!("{}", USAGE);
+                    process::exit(2);
+                });
+            }
+            path => paths.push(path.to_owned()),
+        }
+        i += 1;
+    }
+
+    if paths.len() != 2 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let reference = File::open(&paths[1]).unwrap_or_else(|e| {
+        eprintln!("Couldn't open {}: {}", paths[1], e);
+        process::exit(2);
+    });
+
+    let mut diff = TraceDiff {
+        reference: BufReader::new(reference).lines(),
+        history: VecDeque::with_capacity(context),
+        context,
+        line_number: 0,
+    };
+
+    let mut cpu = cpu::init_cpu();
+    let cartridge = Cartridge::load(&paths[0]).unwrap_or_else(|e| {
+        eprintln!("Couldn't read {}: {}", paths[0], e);
+        process::exit(2);
+    });
+    let mut mmu = Mmu::new(cartridge);
+
+    load_post_boot_registers(&mut cpu.regs);
+    // Gameboy Doctor logs are produced with LY stubbed to 0x90
+    mmu[0xFF44] = 0x90;
+
+    cpu.set_tracer(Some(Tracer::with_callback(move |line| diff.check(line))));
+    cpu.run(&mut mmu);
+
+    println!("Execution stopped at PC={:#06x} before the end of the reference log", cpu.regs.pc);
+    exit(1);
+}
@@ -0,0 +1,100 @@
+//! What the CPU is connected to. `Mmu` is the Game Boy memory map with its peripherals,
+//! `FlatBus` is plain RAM for unit tests.
+
+use std::ops::{Index, IndexMut};
+
+pub trait Bus {
+    /// Reads a byte, with the side effects of a CPU read.
+    fn read(&mut self, address: u16) -> u8;
+
+    /// Writes a byte, with the side effects of a CPU write.
+    fn write(&mut self, address: u16, value: u8);
+
+    /// Advances the hardware clocked alongside the CPU by the given number of clock cycles.
+    fn tick(&mut self, cycles: u8);
+
+    /// Reads a byte without side effects.
+    fn peek(&self, address: u16) -> u8;
+
+    /// Writes a byte without side effects.
+    fn poke(&mut self, address: u16, value: u8);
+
+    /// Reads an opcode byte.
+    fn fetch(&mut self, address: u16) -> u8 {
+        self.read(address)
+    }
+
+    /// Sets the address of the instruction being executed.
+    fn set_pc(&mut self, _pc: u16) {}
+
+    /// Resets the DIV counter, as done by STOP.
+    fn reset_div(&mut self) {}
+}
+
+/// 64 KiB of RAM, without any memory-mapped register.
+pub struct FlatBus {
+    memory: Vec<u8>,
+    cycles: u64,
+}
+
+impl FlatBus {
+    pub fn new() -> FlatBus {
+        FlatBus {
+            memory: vec![0; 0x10000],
+            cycles: 0,
+        }
+    }
+
+    /// Copies `program` to memory from `address`.
+    pub fn load(&mut self, address: u16, program: &[u8]) {
+        let start = address as usize;
+        self.memory[start..start + program.len()].copy_from_slice(program);
+    }
+
+    /// Number of clock cycles the bus was ticked.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+}
+
+impl Default for FlatBus {
+    fn default() -> Self {
+        FlatBus::new()
+    }
+}
+
+impl Bus for FlatBus {
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.memory[address as usize] = value;
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.cycles += cycles as u64;
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn poke(&mut self, address: u16, value: u8) {
+        self.memory[address as usize] = value;
+    }
+}
+
+impl Index<u16> for FlatBus {
+    type Output = u8;
+
+    fn index(&self, index: u16) -> &Self::Output {
+        &self.memory[index as usize]
+    }
+}
+
+impl IndexMut<u16> for FlatBus {
+    fn index_mut(&mut self, index: u16) -> &mut Self::Output {
+        &mut self.memory[index as usize]
+    }
+}
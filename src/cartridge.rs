@@ -1,19 +1,37 @@
 use std::fs;
+use std::io::{self, ErrorKind};
 
+/// Size of the smallest cartridges, two 16 KiB banks
+pub const MIN_ROM_SIZE: usize = 0x8000;
+
+#[derive(Clone)]
 pub struct Cartridge {
     pub content: Vec<u8>,
 }
 
 impl Cartridge {
     pub fn new(path: &str) -> Cartridge {
-        Cartridge {
-            content: fs::read(path).expect("Couldn't read ROM")
+        Self::load(path).expect("Couldn't read ROM")
+    }
+
+    /// Reads a ROM file, which has to be at least `MIN_ROM_SIZE` bytes long.
+    pub fn load(path: &str) -> io::Result<Cartridge> {
+        let content = fs::read(path)?;
+        if content.len() < MIN_ROM_SIZE {
+            return Err(io::Error::new(ErrorKind::InvalidData,
+                                      format!("ROM is {} bytes long, smaller than a cartridge", content.len())));
         }
+
+        Ok(Cartridge {
+            content
+        })
     }
 
+    /// Title from the header, empty if the ROM is too short to have one.
     pub fn name(&self) -> String {
-        String::from_utf8(self.content[0x0134..0x0142].to_vec())
-            .expect("Couldn't read ROM name")
+        self.content.get(0x0134..0x0142)
+            .map(|title| String::from_utf8_lossy(title).into_owned())
+            .unwrap_or_default()
     }
 }
 
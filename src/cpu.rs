@@ -1,15 +1,104 @@
 use std::ops::{BitAnd, BitXor, Index, IndexMut};
+use std::str::FromStr;
 
 use InstructionType::*;
 
 use crate::cpu::Flag::{C, H, N, Z};
-use crate::memory::Mmu;
+use crate::alu;
+use crate::alu::Alu8;
+use crate::bus::Bus;
+use crate::opcodes;
 use crate::opcodes::{InstructionType, FlagId, Instruction, Operand, Register16Id, RegisterId};
 use crate::opcodes::Register16Id::HL;
+use crate::trace::Tracer;
+
+/// Number of clock cycles needed by the LCD to draw a frame
+pub const CYCLES_PER_FRAME: u64 = 70224;
+
+/// Joypad register
+const P1: u16 = 0xFF00;
+/// Interrupt requests
+const IF: u16 = 0xFF0F;
+/// Enabled interrupts
+const IE: u16 = 0xFFFF;
+/// CGB speed switch register: bit 7 is the current speed, bit 0 arms a switch on STOP
+const KEY1: u16 = 0xFF4D;
 
 pub struct Cpu {
     /// CPU registers
     pub regs: Registers,
+
+    tracer: Option<Tracer>,
+
+    /// Number of clock cycles elapsed since the CPU was started
+    cycles: u64,
+
+    /// Whether the conditional branch of the current instruction was taken
+    branch_taken: bool,
+
+    /// Opcode acting as a software breakpoint, like `LD B,B` for Mooneye test ROMs
+    breakpoint_opcode: Option<u8>,
+
+    /// Opcode ending the program when it's about to be executed, for test programs
+    exit_opcode: Option<u8>,
+
+    /// Whether an infinite `JR -2` loop ends the program
+    stop_on_jr_loop: bool,
+
+    /// Interrupt master enable, set by EI and RETI, and cleared by DI
+    ime: bool,
+
+    /// Whether EI was just executed. It only sets IME after the next instruction.
+    ime_pending: bool,
+
+    /// Whether the CPU waits for an interrupt, as done by HALT
+    halted: bool,
+
+    /// Whether the CPU is in the low-power mode entered by STOP
+    stopped: bool,
+
+    illegal_opcode_policy: IllegalOpcodePolicy,
+
+    /// Whether the CPU hung on an illegal opcode
+    locked: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepResult {
+    /// The instruction was executed, the program can go on
+    Continue,
+    /// The program reached the exit opcode or an infinite `JR -2` loop
+    Stopped,
+    /// The software breakpoint opcode was executed, or an illegal opcode was trapped
+    Breakpoint,
+    /// An illegal opcode was reached with `IllegalOpcodePolicy::Error`, and not executed
+    IllegalOpcode { pc: u16, opcode: u8 },
+}
+
+/// What to do with the opcodes that don't exist (0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB-0xED,
+/// 0xF4, 0xFC and 0xFD).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum IllegalOpcodePolicy {
+    /// Hang the CPU like the hardware does, while the rest of the machine keeps running
+    #[default]
+    Lock,
+    /// Report `StepResult::IllegalOpcode`
+    Error,
+    /// Report `StepResult::Breakpoint`, with PC on the opcode, for debuggers to stop there
+    Trap,
+}
+
+impl FromStr for IllegalOpcodePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lock" => Ok(IllegalOpcodePolicy::Lock),
+            "error" => Ok(IllegalOpcodePolicy::Error),
+            "trap" => Ok(IllegalOpcodePolicy::Trap),
+            _ => Err(format!("Unknown illegal opcode policy '{}'", s)),
+        }
+    }
 }
 
 pub struct Registers {
@@ -30,6 +119,130 @@ pub struct Registers {
     pub flags: FlagRegister,
 }
 
+/// Registers and execution state of the CPU, which tools and tests can inspect and restore
+/// with `Cpu::state` and `Cpu::set_state`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct CpuState {
+    pub a: u8,
+    /// Flags in the upper nibble (Z, N, H, C), the lower nibble always reads 0
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    /// Interrupt master enable
+    pub ime: bool,
+    /// Waiting for an interrupt after HALT
+    pub halted: bool,
+    /// In the low-power mode entered by STOP
+    pub stopped: bool,
+}
+
+impl CpuState {
+    pub fn af(&self) -> u16 {
+        u16::from_be_bytes([self.a, self.f & 0xF0])
+    }
+
+    pub fn bc(&self) -> u16 {
+        u16::from_be_bytes([self.b, self.c])
+    }
+
+    pub fn de(&self) -> u16 {
+        u16::from_be_bytes([self.d, self.e])
+    }
+
+    pub fn hl(&self) -> u16 {
+        u16::from_be_bytes([self.h, self.l])
+    }
+
+    pub fn set_af(&mut self, value: u16) {
+        [self.a, self.f] = value.to_be_bytes();
+        self.f &= 0xF0;
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        [self.b, self.c] = value.to_be_bytes();
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        [self.d, self.e] = value.to_be_bytes();
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        [self.h, self.l] = value.to_be_bytes();
+    }
+
+    pub fn flag(&self, flag: Flag) -> bool {
+        self.f & flag.mask() != 0
+    }
+
+    pub fn set_flag(&mut self, flag: Flag, value: bool) {
+        if value {
+            self.f |= flag.mask();
+        } else {
+            self.f &= !flag.mask();
+        }
+    }
+}
+
+impl From<&Registers> for CpuState {
+    fn from(regs: &Registers) -> Self {
+        CpuState {
+            a: regs.a,
+            f: regs.flags.get_f(),
+            b: regs.b,
+            c: regs.c,
+            d: regs.d,
+            e: regs.e,
+            h: regs.h,
+            l: regs.l,
+            sp: regs.sp,
+            pc: regs.pc,
+            ..CpuState::default()
+        }
+    }
+}
+
+impl From<CpuState> for Registers {
+    fn from(state: CpuState) -> Self {
+        let mut flags = FlagRegister { z: false, n: false, h: false, c: false };
+        flags.set_f(state.f);
+
+        Registers {
+            a: state.a,
+            b: state.b,
+            c: state.c,
+            d: state.d,
+            e: state.e,
+            h: state.h,
+            l: state.l,
+            sp: state.sp,
+            pc: state.pc,
+            flags,
+        }
+    }
+}
+
+impl From<&Cpu> for CpuState {
+    fn from(cpu: &Cpu) -> Self {
+        cpu.state()
+    }
+}
+
+/// A CPU in the given state, with the default settings of `init_cpu`.
+impl From<CpuState> for Cpu {
+    fn from(state: CpuState) -> Self {
+        let mut cpu = init_cpu();
+        cpu.set_state(state);
+        cpu
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Flag {
     Z,
     N,
@@ -69,6 +282,18 @@ impl IndexMut<RegisterId> for Registers {
     }
 }
 
+impl Flag {
+    /// Bit of the flag in F.
+    fn mask(self) -> u8 {
+        match self {
+            Z => 0x80,
+            N => 0x40,
+            H => 0x20,
+            C => 0x10,
+        }
+    }
+}
+
 impl Index<Flag> for Registers {
     type Output = bool;
 
@@ -175,504 +400,693 @@ pub fn init_cpu() -> Cpu {
                 c: false,
             },
         },
+        tracer: None,
+        cycles: 0,
+        branch_taken: false,
+        breakpoint_opcode: None,
+        exit_opcode: None,
+        stop_on_jr_loop: true,
+        ime: false,
+        ime_pending: false,
+        halted: false,
+        stopped: false,
+        illegal_opcode_policy: IllegalOpcodePolicy::Lock,
+        locked: false,
     }
 }
 
 impl Cpu {
-    pub fn run(self: &mut Cpu, mmu: &mut Mmu) {
-        'execution: loop {
-            let instr = Instruction::try_from((mmu[self.regs.pc], mmu[self.regs.pc + 1]))
-                .unwrap_or_else(|_| {
-                    if mmu[self.regs.pc] == 0xCB {
-                        panic!("Unsupported opcode {:#04x} {:#04x}", mmu[self.regs.pc], mmu[self.regs.pc + 1])
-                    } else {
-                        panic!("Unsupported opcode {:#04x}", mmu[self.regs.pc])
-                    }
-                });
+    /// Installs a tracer that logs every executed instruction, or removes it with `None`.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
 
-            if mmu[self.regs.pc] != 0x00 && false {
-                println!("PC={:#06x}, SP={:#06x}, A={:#04x}, B={:#04x}, C={:#04x}, D={:#04x}, E={:#04x}, H={:#04x}, L={:#04x}, Z={}, N={}, H={}, C={}, opcode={:#04x} {:?}, ly={:#04x}",
-                         self.regs.pc, self.regs.sp,
-                         self.regs.a, self.regs.b, self.regs.c, self.regs.d, self.regs.e, self.regs.h, self.regs.l,
-                         self.regs[Z], self.regs[N], self.regs[H], self.regs[C],
-                         mmu[self.regs.pc], instr.mnemonic, mmu[0xFF44]
-                );
-            }
+    pub fn tracer_mut(&mut self) -> Option<&mut Tracer> {
+        self.tracer.as_mut()
+    }
 
-            if mmu[self.regs.pc] == 0xCB {
-                self.advance_pc(2);
-            } else {
-                self.advance_pc(1);
-            }
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
 
-            match instr.kind {
-                ADD => {
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    let (add, carry) = calc_with_carry(vec![self.regs.a, n, 0], |a, b| a.overflowing_add(b));
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
 
-                    self.regs[Z] = add == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = half_carry_8_add(self.regs.a, n, 0);
-                    self.regs[C] = carry;
+    pub fn state(&self) -> CpuState {
+        CpuState {
+            ime: self.ime,
+            halted: self.halted,
+            stopped: self.stopped,
+            ..CpuState::from(&self.regs)
+        }
+    }
 
-                    self.regs.a = add;
-                }
-                ADD16 => {
-                    if instr.mnemonic == "ADD SP,r8" {
-                        let n = self.read_8(mmu) as i8 as i16 as u16;
-                        let h = (self.regs.sp & 0x000F) + (n & 0x000F) > 0x000F;
-                        let c = (self.regs.sp & 0x00FF) + (n & 0x00FF) > 0x00FF;
+    /// Loads registers and execution state. The lower nibble of F is ignored, and an EI
+    /// waiting for the next instruction is cancelled.
+    pub fn set_state(&mut self, state: CpuState) {
+        self.regs = Registers::from(state);
+        self.ime = state.ime;
+        self.ime_pending = false;
+        self.halted = state.halted;
+        self.stopped = state.stopped;
+    }
 
-                        self.regs[Z] = false;
-                        self.regs[N] = false;
-                        self.regs[H] = h;
-                        self.regs[C] = c;
+    /// Makes `step` report `StepResult::Breakpoint` after executing the given opcode, or
+    /// disables software breakpoints with `None`.
+    pub fn set_breakpoint_opcode(&mut self, opcode: Option<u8>) {
+        self.breakpoint_opcode = opcode;
+    }
 
-                        self.regs.sp = self.regs.sp.overflowing_add(n).0;
-                    } else {
-                        let lhs = &instr.lhs.unwrap();
-                        let rhs = &instr.rhs.unwrap();
-                        let left = self.get_16bit_operand(lhs, mmu);
-                        let right = self.get_16bit_operand(rhs, mmu);
+    /// Makes `step` report `StepResult::Stopped` instead of executing the given opcode, which
+    /// gives test programs an explicit way to end. Illegal opcodes like 0xFD are good choices.
+    pub fn set_exit_opcode(&mut self, opcode: Option<u8>) {
+        self.exit_opcode = opcode;
+    }
 
-                        let hc = half_carry_16_add(left, right, 0);
-                        let result = left.overflowing_add(right);
+    /// Test ROMs end with an infinite `JR -2` loop, so by default `step` reports it as
+    /// `StepResult::Stopped`. Games also use it to wait for interrupts, so it can be disabled.
+    pub fn set_stop_on_jr_loop(&mut self, enabled: bool) {
+        self.stop_on_jr_loop = enabled;
+    }
 
-                        self.regs[N] = false;
-                        self.regs[H] = hc;
-                        self.regs[C] = result.1;
+    pub fn set_illegal_opcode_policy(&mut self, policy: IllegalOpcodePolicy) {
+        self.illegal_opcode_policy = policy;
+    }
 
-                        self.set_16bit_value(mmu, lhs, result.0);
-                    }
-                }
-                ADC => {
-                    let carry = if self.regs[C] { 1 } else { 0 };
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    let (add, new_carry) = calc_with_carry(vec![self.regs.a, n, carry], |a, b| a.overflowing_add(b));
+    /// Whether the CPU hung on an illegal opcode. Only a reset gets it out of this state.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Whether the CPU is in the low-power mode entered by STOP, until a button is pressed.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Runs the program until it reaches the exit opcode, enters an infinite `JR -2` loop, hits
+    /// the software breakpoint or an illegal opcode.
+    pub fn run(self: &mut Cpu, bus: &mut impl Bus) {
+        while self.step(bus) == StepResult::Continue && !self.locked {}
+    }
+
+    /// Executes a single instruction. The rest of the machine is advanced by one M-cycle
+    /// (4 clock cycles) before every memory access, and for the internal delays of the
+    /// instruction once it is done.
+    pub fn step(self: &mut Cpu, bus: &mut impl Bus) -> StepResult {
+        if self.locked {
+            self.tick(bus);
+            return StepResult::Continue;
+        }
+
+        if self.stopped {
+            return self.step_stopped(bus);
+        }
+
+        if self.halted {
+            return self.step_halted(bus);
+        }
+
+        if let Some(tracer) = &mut self.tracer {
+            tracer.trace(&self.regs, bus);
+        }
+
+        if self.exit_opcode.is_some() && self.exit_opcode == Some(bus.peek(self.regs.pc)) {
+            return StepResult::Stopped;
+        }
+
+        // Prefixed opcodes all exist, so the second byte doesn't matter
+        if opcodes::decode(bus.peek(self.regs.pc), 0x00).is_none() {
+            return self.illegal_opcode(bus);
+        }
+
+        bus.set_pc(self.regs.pc);
+
+        let start = self.cycles;
+        self.branch_taken = false;
 
-                    self.regs[Z] = add == 0;
+        let opcode = self.fetch(bus, self.regs.pc);
+        let cb_opcode = if opcode == 0xCB { self.fetch(bus, self.regs.pc.wrapping_add(1)) } else { 0x00 };
+        let instr = opcodes::decode(opcode, cb_opcode)
+            .unwrap_or_else(|| panic!("Unsupported opcode {:#04x}", opcode));
+
+        self.advance_pc(instr.opcode_size() as i16);
+
+        // EI takes effect after the following instruction, unless it's a DI
+        let enable_ime = self.ime_pending;
+        let result = self.execute(instr, bus);
+        if enable_ime && self.ime_pending {
+            self.ime = true;
+            self.ime_pending = false;
+        }
+
+        let duration = if self.branch_taken { instr.cycles_taken() } else { instr.cycles } as u64;
+        debug_assert!(self.cycles - start <= duration, "{} accesses memory more than it takes cycles", instr.mnemonic);
+        while self.cycles - start < duration {
+            self.tick(bus);
+        }
+
+        if result == StepResult::Continue && !instr.is_prefixed() && self.breakpoint_opcode == Some(opcode) {
+            return StepResult::Breakpoint;
+        }
+
+        result
+    }
+
+    fn execute(&mut self, instr: &Instruction, bus: &mut impl Bus) -> StepResult {
+        match instr.kind {
+            ADD => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                let result = alu::add8(self.regs.a, n, false);
+
+                self.set_flags(result);
+                self.regs.a = result.value;
+            }
+            ADD16 => {
+                // ADD SP,r8 is the only one taking an immediate operand
+                if let Some(Operand::Byte) = instr.rhs {
+                    let n = self.read_8(bus);
+                    let result = alu::add_sp(self.regs.sp, n);
+
+                    self.regs[Z] = false;
                     self.regs[N] = false;
-                    self.regs[H] = half_carry_8_add(self.regs.a, n, carry);
-                    self.regs[C] = new_carry;
+                    self.regs[H] = result.h;
+                    self.regs[C] = result.c;
+
+                    self.regs.sp = result.value;
+                } else {
+                    let lhs = &instr.lhs.unwrap();
+                    let rhs = &instr.rhs.unwrap();
+                    let left = self.get_16bit_operand(lhs, bus);
+                    let right = self.get_16bit_operand(rhs, bus);
+
+                    let result = alu::add16(left, right);
 
-                    self.regs.a = add;
-                }
-                AND => {
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    self.regs.a = self.regs.a.bitand(n);
-                    self.regs[Z] = self.regs.a == 0;
                     self.regs[N] = false;
-                    self.regs[H] = true;
-                    self.regs[C] = false;
+                    self.regs[H] = result.h;
+                    self.regs[C] = result.c;
+
+                    self.set_16bit_value(bus, lhs, result.value);
                 }
-                BIT => {
-                    let bit = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    let n = self.get_operand(&instr.rhs.unwrap(), mmu);
+            }
+            ADC => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                let result = alu::add8(self.regs.a, n, self.regs[C]);
 
-                    self.regs[Z] = n & (1 << bit) == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = true;
+                self.set_flags(result);
+                self.regs.a = result.value;
+            }
+            AND => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                self.regs.a = self.regs.a.bitand(n);
+                self.regs[Z] = self.regs.a == 0;
+                self.regs[N] = false;
+                self.regs[H] = true;
+                self.regs[C] = false;
+            }
+            BIT => {
+                let bit = self.get_operand(&instr.lhs.unwrap(), bus);
+                let n = self.get_operand(&instr.rhs.unwrap(), bus);
+
+                self.regs[Z] = n & (1 << bit) == 0;
+                self.regs[N] = false;
+                self.regs[H] = true;
+            }
+            CALL => {
+                let cond = match &instr.lhs.unwrap() {
+                    Operand::Flag(flag) => self.regs.flags.get(flag),
+                    _ => true
+                };
+                let addr = self.read_16(bus);
+
+                if cond {
+                    self.branch_taken = true;
+                    self.push_stack(self.regs.pc, bus);
+                    self.regs.pc = addr;
                 }
-                CALL => {
-                    let cond = match &instr.lhs.unwrap() {
-                        Operand::Flag(flag) => self.regs.flags.get(flag),
-                        _ => true
-                    };
-                    let addr = self.read_16(mmu);
-
-                    if cond {
-                        self.push_stack(self.regs.pc, mmu);
-                        self.regs.pc = addr;
+            }
+            CCF => {
+                self.regs[C] = !self.regs[C];
+                self.regs[N] = false;
+                self.regs[H] = false;
+            }
+            CP => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                self.set_flags(alu::sub8(self.regs.a, n, false));
+            }
+            CPL => {
+                self.regs.a = !self.regs.a;
+                self.regs[N] = true;
+                self.regs[H] = true;
+            }
+            DAA => {
+                // Explanation at https://ehaskins.com/2018-01-30%20Z80%20DAA/
+                let lo = self.regs.a & 0x0F;
+                let mut added = 0;
+
+                if self.regs.flags.n {
+                    // subtraction
+                    if self.regs.flags.h {
+                        added = 0x06;
                     }
-                }
-                CCF => {
-                    self.regs[C] = !self.regs[C];
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                }
-                CP => {
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    self.regs[Z] = self.regs.a == n;
-                    self.regs[N] = true;
-                    self.regs[H] = half_carry_8_sub(self.regs.a, n, 0);
-                    self.regs[C] = self.regs.a < n;
-                }
-                CPL => {
-                    self.regs.a = !self.regs.a;
-                    self.regs[N] = true;
-                    self.regs[H] = true;
-                }
-                DAA => {
-                    // Explanation at https://ehaskins.com/2018-01-30%20Z80%20DAA/
-                    let lo = self.regs.a & 0x0F;
-                    let mut added = 0;
-
-                    if self.regs.flags.n {
-                        // subtraction
-                        if self.regs.flags.h {
-                            added = 0x06;
-                        }
-                        if self.regs.flags.c {
-                            added += 0x60;
-                        }
-
-                        self.regs.a = self.regs.a.wrapping_sub(added);
-                    } else {
-                        // addition
-                        if self.regs.flags.h || lo > 0x9 {
-                            added = 0x06;
-                        }
-                        if self.regs.flags.c || self.regs.a > 0x99 {
-                            added += 0x60;
-                        }
-
-                        self.regs.a = self.regs.a.wrapping_add(added);
+                    if self.regs.flags.c {
+                        added += 0x60;
                     }
 
-                    self.regs.flags.z = self.regs.a == 0;
-                    self.regs.flags.h = false;
-                    self.regs.flags.c = added >= 0x60;
-                }
-                DEC => {
-                    let op = instr.lhs.unwrap();
-                    match op {
-                        Operand::Register16(reg) => self.regs.set(reg, self.regs.get(reg).wrapping_sub(1)),
-                        Operand::IndirectAddress(Register16Id::HL) => {
-                            let n = &mut mmu[self.regs.get(Register16Id::HL)];
-                            let old = *n;
-                            *n = (*n).wrapping_sub(1);
-
-                            self.regs[Z] = *n == 0;
-                            self.regs[N] = true;
-                            self.regs[H] = half_carry_8_sub(old, 1, 0);
-                        }
-                        Operand::Register(reg) => {
-                            let val = self.regs[reg];
-                            self.regs[reg] = val.wrapping_sub(1);
-
-                            self.regs[Z] = self.regs[reg] == 0;
-                            self.regs[N] = true;
-                            self.regs[H] = half_carry_8_sub(val, 1, 0);
-                        }
-                        _ => panic!("Operand not supported: {:?}", op),
-                    }
-                }
-                DI => {} // TODO
-                EI => {} // TODO
-                INC => {
-                    let reg = instr.lhs.unwrap();
-                    match reg {
-                        Operand::Register16(reg) => {
-                            let n = self.regs.get(reg);
-                            self.regs.set(reg, n.wrapping_add(1));
-                        }
-                        Operand::IndirectAddress(Register16Id::HL) => {
-                            let n = &mut mmu[self.regs.get(Register16Id::HL)];
-                            let old = *n;
-                            *n = (*n).wrapping_add(1);
-
-                            self.regs[Z] = *n == 0;
-                            self.regs[N] = false;
-                            self.regs[H] = half_carry_8_add(old, 1, 0);
-                        }
-                        Operand::Register(reg) => {
-                            let n = self.regs[reg];
-                            self.regs[reg] = n.wrapping_add(1);
-
-                            self.regs[Z] = self.regs[reg] == 0;
-                            self.regs[N] = false;
-                            self.regs[H] = half_carry_8_add(n, 1, 0);
-                        }
-                        _ => panic!("Can't INC this register!")
+                    self.regs.a = self.regs.a.wrapping_sub(added);
+                } else {
+                    // addition
+                    if self.regs.flags.h || lo > 0x9 {
+                        added = 0x06;
                     }
-                }
-                JP => {
-                    let lhs = &instr.lhs.unwrap();
-                    let cond = match lhs {
-                        Operand::Flag(flag) => self.regs.flags.get(flag),
-                        _ => true
-                    };
-
-                    let addr = match lhs {
-                        Operand::Flag(_) => self.get_16bit_operand( &instr.rhs.unwrap(), mmu),
-                        _ => self.get_16bit_operand( lhs, mmu),
-                    };
-                    if cond {
-                        self.set_pc(addr);
+                    if self.regs.flags.c || self.regs.a > 0x99 {
+                        added += 0x60;
                     }
+
+                    self.regs.a = self.regs.a.wrapping_add(added);
                 }
-                JR => {
-                    let cond = match &instr.lhs.unwrap() {
-                        Operand::Flag(flag) => self.regs.flags.get(flag),
-                        _ => true
-                    };
-
-                    let offset = self.read_8(mmu);
-                    if offset as i8 == -2 {
-                        // JR loop, used by test ROMs to indicate end of tests
-                        break 'execution;
+
+                self.regs.flags.z = self.regs.a == 0;
+                self.regs.flags.h = false;
+                self.regs.flags.c = added >= 0x60;
+            }
+            DEC => {
+                let op = instr.lhs.unwrap();
+                match op {
+                    Operand::Register16(reg) => self.regs.set(reg, self.regs.get(reg).wrapping_sub(1)),
+                    Operand::IndirectAddress(Register16Id::HL) => {
+                        let addr = self.regs.get(Register16Id::HL);
+                        let result = alu::dec8(self.read(bus, addr));
+                        self.write(bus, addr, result.value);
+
+                        self.set_flags_except_carry(result);
                     }
-                    if cond {
-                        self.advance_pc(offset as i8 as i16);
+                    Operand::Register(reg) => {
+                        let result = alu::dec8(self.regs[reg]);
+                        self.regs[reg] = result.value;
+
+                        self.set_flags_except_carry(result);
                     }
+                    _ => panic!("Operand not supported: {:?}", op),
                 }
-                LD => {
-                    let value = self.get_operand(&instr.rhs.unwrap(), mmu);
-                    self.set_value(mmu, &instr.lhs.unwrap(), value);
-                }
-                LD16 => {
-                    let rhs = &instr.rhs.unwrap();
-
-
-                    match rhs {
-                        Operand::SpOffset => {
-                            let sp = self.regs.sp;
-                            let n = self.read_8(mmu) as i8 as i16 as u16;
-
-                            self.regs[Z] = false;
-                            self.regs[N] = false;
-                            self.regs[H] = (sp & 0x000F) + (n & 0x000F) > 0x000F;
-                            self.regs[C] = (sp & 0x00FF) + (n & 0x00FF) > 0x00FF;
+            }
+            // Interrupts aren't dispatched yet, only IME is kept track of
+            DI => {
+                self.ime = false;
+                self.ime_pending = false;
+            }
+            EI => self.ime_pending = true,
+            HALT => self.halted = true,
+            INC => {
+                let reg = instr.lhs.unwrap();
+                match reg {
+                    Operand::Register16(reg) => {
+                        let n = self.regs.get(reg);
+                        self.regs.set(reg, n.wrapping_add(1));
+                    }
+                    Operand::IndirectAddress(Register16Id::HL) => {
+                        let addr = self.regs.get(Register16Id::HL);
+                        let result = alu::inc8(self.read(bus, addr));
+                        self.write(bus, addr, result.value);
 
-                            self.set_16bit_value(mmu, &instr.lhs.unwrap(), sp.wrapping_add(n));
-                        }
-                        _ => {
-                            let value = self.get_16bit_operand(rhs, mmu);
-                            self.set_16bit_value(mmu, &instr.lhs.unwrap(), value);
-                        }
+                        self.set_flags_except_carry(result);
                     }
-                }
-                LDD => {
-                    let value = self.get_operand(&instr.rhs.unwrap(), mmu);
-                    self.set_value(mmu, &instr.lhs.unwrap(), value);
-                    self.regs.set(HL, self.regs.get(HL).wrapping_sub(1));
-                }
-                LDI => {
-                    let value = self.get_operand(&instr.rhs.unwrap(), mmu);
-                    self.set_value(mmu, &instr.lhs.unwrap(), value);
-                    self.regs.set(HL, self.regs.get(HL).wrapping_add(1));
-                }
-                NOP => {}
-                OR => {
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    self.regs.a = self.regs.a | n;
+                    Operand::Register(reg) => {
+                        let result = alu::inc8(self.regs[reg]);
+                        self.regs[reg] = result.value;
 
-                    self.regs[Z] = self.regs.a == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = false;
-                }
-                POP => {
-                    let val = self.pop_stack(mmu);
-                    self.set_16bit_value(mmu, &instr.lhs.unwrap(), val);
-                }
-                PUSH => {
-                    let addr = self.get_16bit_operand(&instr.lhs.unwrap(), mmu);
-                    self.push_stack(addr, mmu)
-                },
-                RES => {
-                    let lhs = &instr.lhs.unwrap();
-                    let rhs = &instr.rhs.unwrap();
-                    let bit = self.get_operand(lhs, mmu);
-                    let mut n = self.get_operand(rhs, mmu);
-                    n = n & (0xFF ^ (1 << bit));
-                    self.set_value(mmu, rhs, n);
-                }
-                RET => {
-                    let cond = match instr.lhs {
-                        Some(Operand::Flag(flag)) => self.regs.flags.get(&flag),
-                        _ => true
-                    };
-                    if cond {
-                        let addr = self.pop_stack(mmu);
-                        self.set_pc(addr);
+                        self.set_flags_except_carry(result);
                     }
+                    _ => panic!("Can't INC this register!")
                 }
-                RETI => {
-                    let addr = self.pop_stack(mmu);
+            }
+            JP => {
+                let lhs = &instr.lhs.unwrap();
+                let cond = match lhs {
+                    Operand::Flag(flag) => self.regs.flags.get(flag),
+                    _ => true
+                };
+
+                let addr = match lhs {
+                    Operand::Flag(_) => self.get_16bit_operand( &instr.rhs.unwrap(), bus),
+                    _ => self.get_16bit_operand( lhs, bus),
+                };
+                if cond {
+                    self.branch_taken = true;
                     self.set_pc(addr);
-                    // TODO enable interrupts
                 }
-                RL => {
-                    let lhs = &instr.lhs.unwrap();
-                    let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
-                    let val = self.get_operand(lhs, mmu);
-                    let bit7 = val >> 7 != 0;
-                    let rotated = val << 1 | carry_bit;
-                    self.set_value(mmu, lhs, rotated);
-
-                    self.regs[Z] = rotated == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit7;
-                }
-                RLA => {
-                    let bit7 = self.regs.a >> 7 != 0;
-                    let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
-                    self.regs.a = self.regs.a << 1 | carry_bit;
-                    self.regs[Z] = false;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit7;
-                }
-                RLC => {
-                    let lhs = &instr.lhs.unwrap();
-                    let val = self.get_operand(lhs, mmu).rotate_left(1);
-                    self.set_value(mmu, lhs, val);
-                    self.regs[Z] = val == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = (val & 0x01) == 0x01;
+            }
+            JR => {
+                let cond = match &instr.lhs.unwrap() {
+                    Operand::Flag(flag) => self.regs.flags.get(flag),
+                    _ => true
+                };
+
+                let offset = self.read_8(bus);
+                if cond && offset as i8 == -2 && self.stop_on_jr_loop {
+                    // JR loop, used by test ROMs to indicate end of tests
+                    return StepResult::Stopped;
                 }
-                RLCA => {
-                    self.regs.a = self.regs.a.rotate_left(1);
-                    self.regs[Z] = false;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = self.regs.a & 0x01 != 0;
+                if cond {
+                    self.branch_taken = true;
+                    self.advance_pc(offset as i8 as i16);
                 }
-                RR => {
-                    let lhs = &instr.lhs.unwrap();
-                    let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
-                    let val = self.get_operand(lhs, mmu);
-                    let bit0 = val & 0x01 != 0;
-                    self.set_value(mmu, lhs, val >> 1 | carry_bit << 7);
+            }
+            LD => {
+                let value = self.get_operand(&instr.rhs.unwrap(), bus);
+                self.set_value(bus, &instr.lhs.unwrap(), value);
+            }
+            LD16 => {
+                let rhs = &instr.rhs.unwrap();
 
-                    self.regs[Z] = self.get_operand(lhs, mmu) == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit0;
-                }
-                RRA => {
-                    let bit0 = self.regs.a & 0x01 != 0;
-                    let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
-                    self.regs.a = self.regs.a >> 1 | carry_bit << 7;
-                    self.regs[Z] = false;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit0;
-                }
-                RRC => {
-                    let lhs = &instr.lhs.unwrap();
-                    let val = self.get_operand(lhs, mmu).rotate_right(1);
-                    self.set_value(mmu, lhs, val);
-                    self.regs[Z] = val == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = (val & 0x80) == 0x80;
-                }
-                RRCA => {
-                    self.regs.a = self.regs.a.rotate_right(1);
-                    self.regs[Z] = false;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = self.regs.a & 0x80 != 0;
-                }
-                RST => {
-                    self.push_stack(self.regs.pc, mmu);
-                    let offset = self.get_operand(&instr.lhs.unwrap(), mmu) as u16;
-                    self.set_pc(0x0000 + offset);
-                }
-                SBC => {
-                    let carry = if self.regs[C] { 1 } else { 0 };
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
 
-                    let (sub, new_carry) = calc_with_carry(vec![self.regs.a, n, carry], |a, b| a.overflowing_sub(b));
+                match rhs {
+                    Operand::SpOffset => {
+                        let n = self.read_8(bus);
+                        let result = alu::add_sp(self.regs.sp, n);
 
-                    self.regs[Z] = sub == 0;
-                    self.regs[N] = true;
-                    self.regs[H] = half_carry_8_sub(self.regs.a, n, carry);
-                    self.regs[C] = new_carry;
+                        self.regs[Z] = false;
+                        self.regs[N] = false;
+                        self.regs[H] = result.h;
+                        self.regs[C] = result.c;
 
-                    self.regs.a = sub;
+                        self.set_16bit_value(bus, &instr.lhs.unwrap(), result.value);
+                    }
+                    _ => {
+                        let value = self.get_16bit_operand(rhs, bus);
+                        self.set_16bit_value(bus, &instr.lhs.unwrap(), value);
+                    }
                 }
-                SCF => {
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = true;
+            }
+            LDD => {
+                let value = self.get_operand(&instr.rhs.unwrap(), bus);
+                self.set_value(bus, &instr.lhs.unwrap(), value);
+                self.regs.set(HL, self.regs.get(HL).wrapping_sub(1));
+            }
+            LDI => {
+                let value = self.get_operand(&instr.rhs.unwrap(), bus);
+                self.set_value(bus, &instr.lhs.unwrap(), value);
+                self.regs.set(HL, self.regs.get(HL).wrapping_add(1));
+            }
+            NOP => {}
+            OR => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                self.regs.a |= n;
+
+                self.regs[Z] = self.regs.a == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = false;
+            }
+            POP => {
+                let val = self.pop_stack(bus);
+                self.set_16bit_value(bus, &instr.lhs.unwrap(), val);
+            }
+            PUSH => {
+                let addr = self.get_16bit_operand(&instr.lhs.unwrap(), bus);
+                self.push_stack(addr, bus)
+            },
+            RES => {
+                let lhs = &instr.lhs.unwrap();
+                let rhs = &instr.rhs.unwrap();
+                let bit = self.get_operand(lhs, bus);
+                let mut n = self.get_operand(rhs, bus);
+                n &= 0xFF ^ (1 << bit);
+                self.set_value(bus, rhs, n);
+            }
+            RET => {
+                let cond = match instr.lhs {
+                    Some(Operand::Flag(flag)) => self.regs.flags.get(&flag),
+                    _ => true
+                };
+                // The condition is checked during an internal M-cycle, before the pops
+                if instr.is_conditional() {
+                    self.tick(bus);
                 }
-                SET => {
-                    let lhs = &instr.lhs.unwrap();
-                    let rhs = &instr.rhs.unwrap();
-                    let bit = self.get_operand(lhs, mmu);
-                    let mut n = self.get_operand(rhs, mmu);
-                    n = n | (1 << bit);
-                    self.set_value(mmu, rhs, n);
+                if cond {
+                    self.branch_taken = true;
+                    let addr = self.pop_stack(bus);
+                    self.set_pc(addr);
                 }
-                SLA => {
-                    let lhs = &instr.lhs.unwrap();
-                    let val = self.get_operand(lhs, mmu);
-                    let bit7 = val & 0x80 == 0x80;
-                    let shifted = val << 1;
+            }
+            RETI => {
+                let addr = self.pop_stack(bus);
+                self.set_pc(addr);
+                self.ime = true;
+            }
+            RL => {
+                let lhs = &instr.lhs.unwrap();
+                let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
+                let val = self.get_operand(lhs, bus);
+                let bit7 = val >> 7 != 0;
+                let rotated = val << 1 | carry_bit;
+                self.set_value(bus, lhs, rotated);
+
+                self.regs[Z] = rotated == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit7;
+            }
+            RLA => {
+                let bit7 = self.regs.a >> 7 != 0;
+                let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
+                self.regs.a = self.regs.a << 1 | carry_bit;
+                self.regs[Z] = false;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit7;
+            }
+            RLC => {
+                let lhs = &instr.lhs.unwrap();
+                let val = self.get_operand(lhs, bus).rotate_left(1);
+                self.set_value(bus, lhs, val);
+                self.regs[Z] = val == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = (val & 0x01) == 0x01;
+            }
+            RLCA => {
+                self.regs.a = self.regs.a.rotate_left(1);
+                self.regs[Z] = false;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = self.regs.a & 0x01 != 0;
+            }
+            RR => {
+                let lhs = &instr.lhs.unwrap();
+                let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
+                let val = self.get_operand(lhs, bus);
+                let bit0 = val & 0x01 != 0;
+                let rotated = val >> 1 | carry_bit << 7;
+                self.set_value(bus, lhs, rotated);
+
+                self.regs[Z] = rotated == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit0;
+            }
+            RRA => {
+                let bit0 = self.regs.a & 0x01 != 0;
+                let carry_bit = if self.regs[C] { 1 } else { 0 } as u8;
+                self.regs.a = self.regs.a >> 1 | carry_bit << 7;
+                self.regs[Z] = false;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit0;
+            }
+            RRC => {
+                let lhs = &instr.lhs.unwrap();
+                let val = self.get_operand(lhs, bus).rotate_right(1);
+                self.set_value(bus, lhs, val);
+                self.regs[Z] = val == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = (val & 0x80) == 0x80;
+            }
+            RRCA => {
+                self.regs.a = self.regs.a.rotate_right(1);
+                self.regs[Z] = false;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = self.regs.a & 0x80 != 0;
+            }
+            RST => {
+                self.push_stack(self.regs.pc, bus);
+                let offset = self.get_operand(&instr.lhs.unwrap(), bus) as u16;
+                self.set_pc(offset);
+            }
+            SBC => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                let result = alu::sub8(self.regs.a, n, self.regs[C]);
 
-                    self.set_value(mmu, lhs, shifted);
+                self.set_flags(result);
+                self.regs.a = result.value;
+            }
+            SCF => {
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = true;
+            }
+            SET => {
+                let lhs = &instr.lhs.unwrap();
+                let rhs = &instr.rhs.unwrap();
+                let bit = self.get_operand(lhs, bus);
+                let mut n = self.get_operand(rhs, bus);
+                n |= 1 << bit;
+                self.set_value(bus, rhs, n);
+            }
+            SLA => {
+                let lhs = &instr.lhs.unwrap();
+                let val = self.get_operand(lhs, bus);
+                let bit7 = val & 0x80 == 0x80;
+                let shifted = val << 1;
+
+                self.set_value(bus, lhs, shifted);
+
+                self.regs[Z] = shifted == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit7;
+            }
+            SRA => {
+                let lhs = &instr.lhs.unwrap();
+                let val = self.get_operand(lhs, bus);
+                let bit7 = val & 0x80;
+                let bit0 = val & 0x01 == 0x01;
+                let shifted = (val >> 1) | bit7;
+
+                self.set_value(bus, lhs, shifted);
+
+                self.regs[Z] = shifted == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit0;
+            }
+            SRL => {
+                let lhs = &instr.lhs.unwrap();
+                let val = self.get_operand(lhs, bus);
+                let bit0 = val & 0x01 == 0x01;
+                let shifted = val >> 1;
+
+                self.set_value(bus, lhs, shifted);
+
+                self.regs[Z] = shifted == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = bit0;
+            }
+            STOP => self.stop(bus),
+            SUB => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                let result = alu::sub8(self.regs.a, n, false);
 
-                    self.regs[Z] = shifted == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit7;
-                }
-                SRA => {
-                    let lhs = &instr.lhs.unwrap();
-                    let val = self.get_operand(lhs, mmu);
-                    let bit7 = val & 0x80;
-                    let bit0 = val & 0x01 == 0x01;
-                    let shifted = (val >> 1) | bit7;
+                self.set_flags(result);
+                self.regs.a = result.value;
+            }
+            SWAP => {
+                let lhs = &instr.lhs.unwrap();
+                let val = self.get_operand(lhs, bus);
+                let swapped = ((val & 0x0F) << 4) | ((val & 0xF0) >> 4);
+
+                self.set_value(bus, lhs, swapped);
+                self.regs[Z] = swapped == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = false;
+            }
+            XOR => {
+                let n = self.get_operand(&instr.lhs.unwrap(), bus);
+                self.regs.a = self.regs.a.bitxor(n);
+                self.regs[Z] = self.regs.a == 0;
+                self.regs[N] = false;
+                self.regs[H] = false;
+                self.regs[C] = false;
+            }
+            _ => panic!("{:?}", instr.kind)
+        }
 
-                    self.set_value(mmu, lhs, shifted);
+        StepResult::Continue
+    }
 
-                    self.regs[Z] = shifted == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit0;
-                }
-                SRL => {
-                    let lhs = &instr.lhs.unwrap();
-                    let val = self.get_operand(lhs, mmu);
-                    let bit0 = val & 0x01 == 0x01;
-                    let shifted = val >> 1;
+    fn set_flags(&mut self, result: Alu8) {
+        self.set_flags_except_carry(result);
+        self.regs[C] = result.c;
+    }
 
-                    self.set_value(mmu, lhs, shifted);
+    fn set_flags_except_carry(&mut self, result: Alu8) {
+        self.regs[Z] = result.z;
+        self.regs[N] = result.n;
+        self.regs[H] = result.h;
+    }
 
-                    self.regs[Z] = shifted == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = bit0;
-                }
-                STOP => break 'execution,
-                SUB => {
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    let (sub, carry) = calc_with_carry(vec![self.regs.a, n, 0], |a, b| a.overflowing_sub(b));
+    fn illegal_opcode(&mut self, bus: &mut impl Bus) -> StepResult {
+        match self.illegal_opcode_policy {
+            IllegalOpcodePolicy::Lock => {
+                bus.set_pc(self.regs.pc);
+                self.fetch(bus, self.regs.pc);
+                self.locked = true;
+                StepResult::Continue
+            }
+            IllegalOpcodePolicy::Error => StepResult::IllegalOpcode { pc: self.regs.pc, opcode: bus.peek(self.regs.pc) },
+            IllegalOpcodePolicy::Trap => StepResult::Breakpoint,
+        }
+    }
 
-                    self.regs[Z] = sub == 0;
-                    self.regs[N] = true;
-                    self.regs[H] = half_carry_8_sub(self.regs.a, n, 0);
-                    self.regs[C] = carry;
+    /// STOP resets DIV, then either switches the CGB speed if KEY1 is armed, or enters the
+    /// low-power mode.
+    fn stop(&mut self, bus: &mut impl Bus) {
+        // Skips the padding byte
+        self.advance_pc(1);
+        bus.reset_div();
 
-                    self.regs.a = sub;
-                }
-                SWAP => {
-                    let lhs = &instr.lhs.unwrap();
-                    let val = self.get_operand(lhs, mmu);
-                    let swapped = ((val & 0x0F) << 4) | ((val & 0xF0) >> 4);
+        if bus.peek(KEY1) & 0x01 != 0 {
+            bus.poke(KEY1, (bus.peek(KEY1) ^ 0x80) & 0xFE);
+        } else {
+            self.stopped = true;
+        }
+    }
 
-                    self.set_value(mmu, lhs, swapped);
-                    self.regs[Z] = swapped == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = false;
-                }
-                XOR => {
-                    let n = self.get_operand(&instr.lhs.unwrap(), mmu);
-                    self.regs.a = self.regs.a.bitxor(n);
-                    self.regs[Z] = self.regs.a == 0;
-                    self.regs[N] = false;
-                    self.regs[H] = false;
-                    self.regs[C] = false;
-                }
-                _ => panic!("{:?}", instr.kind)
-            }
+    /// In low-power mode, the system clock is stopped until a selected button is pressed,
+    /// which pulls one of the low bits of P1 down.
+    fn step_stopped(&mut self, bus: &mut impl Bus) -> StepResult {
+        if bus.peek(P1) & 0x0F != 0x0F {
+            self.stopped = false;
+        }
+
+        self.cycles += 4;
+        StepResult::Continue
+    }
+
+    /// A halted CPU waits until an enabled interrupt is requested.
+    fn step_halted(&mut self, bus: &mut impl Bus) -> StepResult {
+        self.tick(bus);
+
+        if bus.peek(IE) & bus.peek(IF) & 0x1F != 0 {
+            self.halted = false;
         }
+
+        StepResult::Continue
+    }
+
+    /// Advances the rest of the machine by one M-cycle.
+    fn tick(&mut self, bus: &mut impl Bus) {
+        self.cycles += 4;
+        bus.tick(4);
+    }
+
+    fn fetch(&mut self, bus: &mut impl Bus, address: u16) -> u8 {
+        self.tick(bus);
+        bus.fetch(address)
+    }
+
+    fn read(&mut self, bus: &mut impl Bus, address: u16) -> u8 {
+        self.tick(bus);
+        bus.read(address)
+    }
+
+    fn write(&mut self, bus: &mut impl Bus, address: u16, value: u8) {
+        self.tick(bus);
+        bus.write(address, value);
     }
 
     fn set_pc(self: &mut Cpu, addr: u16) {
@@ -680,124 +1094,105 @@ impl Cpu {
     }
 
     fn advance_pc(self: &mut Cpu, nb_bytes: i16) {
-        self.regs.pc = (self.regs.pc as i16 + nb_bytes) as u16;
+        self.regs.pc = self.regs.pc.wrapping_add_signed(nb_bytes);
     }
 
-    fn read_8(&mut self, mmu: &Mmu) -> u8 {
-        let n = mmu[self.regs.pc];
-        self.regs.pc += 1;
+    fn read_8(&mut self, bus: &mut impl Bus) -> u8 {
+        let n = self.read(bus, self.regs.pc);
+        self.regs.pc = self.regs.pc.wrapping_add(1);
         n
     }
 
-    fn read_16(&mut self, mmu: &Mmu) -> u16 {
-        let n = u16::from_le_bytes([mmu[self.regs.pc], mmu[self.regs.pc + 1]]);
-        self.regs.pc += 2;
+    fn read_16(&mut self, bus: &mut impl Bus) -> u16 {
+        let n = u16::from_le_bytes([self.read(bus, self.regs.pc), self.read(bus, self.regs.pc.wrapping_add(1))]);
+        self.regs.pc = self.regs.pc.wrapping_add(2);
         n
     }
 
-    fn get_operand(&mut self, op: &Operand, mmu: &Mmu) -> u8 {
+    fn get_operand(&mut self, op: &Operand, bus: &mut impl Bus) -> u8 {
         match op {
             Operand::DirectAddress => {
-                let addr = self.read_16(mmu);
-                mmu[addr]
+                let addr = self.read_16(bus);
+                self.read(bus, addr)
             }
             Operand::IndirectAddress(reg) => {
-                mmu[self.regs.get(*reg)]
+                self.read(bus, self.regs.get(*reg))
             }
             Operand::Byte => {
-                self.read_8(mmu)
+                self.read_8(bus)
             }
             Operand::Register(reg) => self.regs[*reg],
             Operand::Value(val) => *val,
-            Operand::IoPort(reg) => mmu[0xFF00 + self.regs[*reg] as u16],
-            Operand::IoPortOffset => mmu[0xFF00 + self.read_8(mmu) as u16],
+            Operand::IoPort(reg) => self.read(bus, 0xFF00 + self.regs[*reg] as u16),
+            Operand::IoPortOffset => {
+                let offset = self.read_8(bus) as u16;
+                self.read(bus, 0xFF00 + offset)
+            }
             _=> panic!("{:?}", op)
         }
     }
 
-    fn get_16bit_operand(&mut self, op: &Operand, mmu: &Mmu) -> u16 {
+    fn get_16bit_operand(&mut self, op: &Operand, bus: &mut impl Bus) -> u16 {
         match op {
             Operand::Register16(reg) => self.regs.get(*reg),
-            Operand::Byte => self.read_16(mmu),
+            Operand::Byte => self.read_16(bus),
             _=> panic!("{:?}", op)
         }
     }
 
-    fn set_value(&mut self, mmu: &mut Mmu, op: &Operand, value: u8) {
+    fn set_value(&mut self, bus: &mut impl Bus, op: &Operand, value: u8) {
         match op {
             Operand::Register(reg) => {
                 self.regs[*reg] = value;
             }
             Operand::IndirectAddress(reg) => {
-                mmu[self.regs.get(*reg)] = value;
+                self.write(bus, self.regs.get(*reg), value);
             }
             Operand::DirectAddress => {
-                let addr = self.read_16(mmu);
-                mmu[addr] = value;
+                let addr = self.read_16(bus);
+                self.write(bus, addr, value);
             }
-            Operand::IoPort(reg) => mmu[0xFF00 + self.regs[*reg] as u16] = value,
+            Operand::IoPort(reg) => self.write(bus, 0xFF00 + self.regs[*reg] as u16, value),
             Operand::IoPortOffset => {
-                let offset = self.read_8(mmu) as u16;
-                mmu[0xFF00 + offset] = value;
+                let offset = self.read_8(bus) as u16;
+                self.write(bus, 0xFF00 + offset, value);
             },
             _ => panic!("{:?}", op),
         }
     }
 
-    fn set_16bit_value(&mut self, mmu: &mut Mmu, op: &Operand, value: u16) {
+    fn set_16bit_value(&mut self, bus: &mut impl Bus, op: &Operand, value: u16) {
         match op {
             Operand::Register16(reg) => {
                 self.regs.set(*reg, value);
             }
             Operand::Byte => {
-                let addr = self.read_16(mmu);
-                [mmu[addr], mmu[addr + 1]] = value.to_le_bytes();
+                let addr = self.read_16(bus);
+                let [lo, hi] = value.to_le_bytes();
+                self.write(bus, addr, lo);
+                self.write(bus, addr.wrapping_add(1), hi);
             }
             _ => panic!("{:?}", op)
         }
     }
 
-    fn push_stack(&mut self, val: u16, mmu: &mut Mmu) {
+    fn push_stack(&mut self, val: u16, bus: &mut impl Bus) {
         let [lo, hi] = val.to_le_bytes();
 
-        self.regs.sp -= 1;
-        mmu[self.regs.sp] = hi;
-        self.regs.sp -= 1;
-        mmu[self.regs.sp] = lo;
-    }
-    fn pop_stack(&mut self, mmu: &mut Mmu) -> u16 {
-        let lo = mmu[self.regs.sp];
-        self.regs.sp += 1;
-        let hi = mmu[self.regs.sp];
-        self.regs.sp += 1;
+        // SP is decremented during an internal M-cycle before the writes
+        self.tick(bus);
 
-        u16::from_le_bytes([lo, hi])
+        self.regs.sp = self.regs.sp.wrapping_sub(1);
+        self.write(bus, self.regs.sp, hi);
+        self.regs.sp = self.regs.sp.wrapping_sub(1);
+        self.write(bus, self.regs.sp, lo);
     }
-}
+    fn pop_stack(&mut self, bus: &mut impl Bus) -> u16 {
+        let lo = self.read(bus, self.regs.sp);
+        self.regs.sp = self.regs.sp.wrapping_add(1);
+        let hi = self.read(bus, self.regs.sp);
+        self.regs.sp = self.regs.sp.wrapping_add(1);
 
-fn calc_with_carry<T: Copy>(operands: Vec<T>, op: fn(T, T) -> (T, bool)) -> (T, bool) {
-    let mut c = false;
-    let mut acc = operands[0];
-    for x in &operands[1..] {
-        if !c {
-            let res = op(acc, *x);
-            acc = res.0;
-            c = res.1;
-        } else {
-            acc = op(acc, *x).0
-        }
+        u16::from_le_bytes([lo, hi])
     }
-    (acc, c)
-}
-
-fn half_carry_8_add(a: u8, b: u8, c: u8) -> bool {
-    (a & 0xF) + (b & 0xF) + c > 0xF
-}
-
-fn half_carry_8_sub(a: u8, b: u8, c: u8) -> bool {
-    (a & 0x0F) < (b & 0x0F) + c
-}
-
-fn half_carry_16_add(a: u16, b: u16, c: u16) -> bool {
-    (a & 0x07FF) + (b & 0x07FF) + c > 0x07FF
 }
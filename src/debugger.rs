@@ -0,0 +1,213 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use crate::cpu::{Cpu, StepResult};
+use crate::memory::{AccessKind, HookId, Mmu};
+use crate::opcodes;
+use crate::opcodes::{Instruction, InstructionType, RegisterId};
+
+/// Drives a `Cpu` one instruction at a time, stopping on breakpoints and watchpoints and
+/// keeping track of the call stack.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+    call_stack: Vec<CallFrame>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub address: u16,
+    pub kind: WatchKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    /// Address of the CALL or RST instruction
+    pub call_site: u16,
+    /// Address of the called subroutine
+    pub target: u16,
+    pub return_address: u16,
+    /// Value of SP right after the return address was pushed
+    pub sp: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// A single step completed
+    Step,
+    /// PC reached a breakpoint, the instruction at that address has not been executed yet
+    Breakpoint(u16),
+    /// The last executed instruction accessed a watched address
+    Watchpoint { address: u16, access: AccessKind, value: u8 },
+    /// The program reached the exit opcode or entered an infinite `JR -2` loop
+    Stopped,
+    /// PC reached an illegal opcode, with `IllegalOpcodePolicy::Error`
+    IllegalOpcode { pc: u16, opcode: u8 },
+    /// The maximum number of instructions was executed
+    Limit,
+}
+
+impl WatchKind {
+    fn kinds(&self) -> &'static [AccessKind] {
+        match self {
+            WatchKind::Read => &[AccessKind::Read],
+            WatchKind::Write => &[AccessKind::Write],
+            WatchKind::ReadWrite => &[AccessKind::Read, AccessKind::Write],
+        }
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger::default()
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = &u16> {
+        self.breakpoints.iter()
+    }
+
+    pub fn add_watchpoint(&mut self, address: u16, kind: WatchKind) {
+        self.remove_watchpoint(address);
+        self.watchpoints.push(Watchpoint { address, kind });
+    }
+
+    pub fn remove_watchpoint(&mut self, address: u16) -> bool {
+        let len = self.watchpoints.len();
+        self.watchpoints.retain(|w| w.address != address);
+        self.watchpoints.len() != len
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Frames of the subroutines currently being executed, innermost last.
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    /// Executes a single instruction, ignoring breakpoints.
+    pub fn step(&mut self, cpu: &mut Cpu, mmu: &mut Mmu) -> StopReason {
+        let pc = cpu.regs.pc;
+        let sp = cpu.regs.sp;
+        let instr = opcodes::decode(mmu[pc], mmu[pc.wrapping_add(1)]);
+
+        let hits = Rc::new(RefCell::new(Vec::new()));
+        let hooks: Vec<HookId> = self.watchpoints.iter()
+            .flat_map(|w| w.kind.kinds().iter().map(|kind| (w.address, *kind)))
+            .map(|(address, kind)| {
+                let hits = hits.clone();
+                mmu.add_hook(kind, address..=address, move |access| hits.borrow_mut().push(*access))
+            })
+            .collect();
+
+        let result = cpu.step(mmu);
+
+        for hook in hooks {
+            mmu.remove_hook(hook);
+        }
+
+        match result {
+            StepResult::Stopped => return StopReason::Stopped,
+            StepResult::IllegalOpcode { pc, opcode } => return StopReason::IllegalOpcode { pc, opcode },
+            _ => {}
+        }
+
+        if let Some(instr) = instr {
+            self.update_call_stack(instr, pc, sp, cpu);
+        }
+
+        let first_hit = hits.borrow().first().copied();
+
+        match first_hit {
+            Some(hit) => StopReason::Watchpoint { address: hit.address, access: hit.kind, value: hit.value },
+            None if result == StepResult::Breakpoint => StopReason::Breakpoint(pc),
+            None => StopReason::Step,
+        }
+    }
+
+    /// Runs until a breakpoint or watchpoint is hit, the program stops or `limit` instructions
+    /// were executed. A breakpoint on the current PC is ignored so that execution can resume
+    /// after it was hit.
+    pub fn resume(&mut self, cpu: &mut Cpu, mmu: &mut Mmu, limit: Option<u64>) -> StopReason {
+        let mut executed = 0;
+
+        loop {
+            if limit.is_some_and(|limit| executed >= limit) {
+                return StopReason::Limit;
+            }
+            if executed > 0 && self.breakpoints.contains(&cpu.regs.pc) {
+                return StopReason::Breakpoint(cpu.regs.pc);
+            }
+
+            let reason = self.step(cpu, mmu);
+            executed += 1;
+
+            if reason != StopReason::Step {
+                return reason;
+            }
+        }
+    }
+
+    fn update_call_stack(&mut self, instr: &Instruction, pc: u16, sp: u16, cpu: &Cpu) {
+        match instr.kind {
+            InstructionType::CALL | InstructionType::RST if cpu.regs.sp == sp.wrapping_sub(2) => {
+                self.call_stack.push(CallFrame {
+                    call_site: pc,
+                    target: cpu.regs.pc,
+                    return_address: pc.wrapping_add(instr.length() as u16),
+                    sp: cpu.regs.sp,
+                });
+            }
+            InstructionType::RET | InstructionType::RETI if cpu.regs.sp == sp.wrapping_add(2) => {
+                // Also drops frames of subroutines that manipulated the stack instead of returning
+                while self.call_stack.last().is_some_and(|frame| frame.sp < cpu.regs.sp) {
+                    self.call_stack.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Disassembles the instruction at `address`, returning its text and length. Unused opcodes
+/// are shown as a `db` directive of length 1.
+pub fn disassemble_at(mmu: &Mmu, address: u16) -> (String, u16) {
+    let bytes = [mmu[address], mmu[address.wrapping_add(1)], mmu[address.wrapping_add(2)]];
+
+    match opcodes::decode(bytes[0], bytes[1]) {
+        Some(instr) => (opcodes::disassemble(&bytes).unwrap_or_default(), instr.length() as u16),
+        None => (format!("db ${:02X}", bytes[0]), 1),
+    }
+}
+
+/// Formats the 8 registers and the flags on a single line.
+pub fn format_registers(cpu: &Cpu) -> String {
+    use RegisterId::*;
+
+    let regs = &cpu.regs;
+    let flag = |set: bool, name: char| if set { name } else { '-' };
+
+    format!(
+        "A={:02X} B={:02X} C={:02X} D={:02X} E={:02X} H={:02X} L={:02X} SP={:04X} PC={:04X} F={}{}{}{}",
+        regs[A], regs[B], regs[C], regs[D], regs[E], regs[H], regs[L], regs.sp, regs.pc,
+        flag(regs.flags.z, 'Z'), flag(regs.flags.n, 'N'), flag(regs.flags.h, 'H'), flag(regs.flags.c, 'C')
+    )
+}
@@ -0,0 +1,266 @@
+//! The whole machine behind a single type, for applications that don't need to wire the
+//! CPU and the MMU together themselves.
+//!
+//! ```no_run
+//! use ruboy::cartridge::Cartridge;
+//! use ruboy::gameboy::GameBoy;
+//! use ruboy::joypad::Button;
+//!
+//! let mut gameboy = GameBoy::builder(Cartridge::new("game.gb"))
+//!     .save_path("game.sav")
+//!     .build()
+//!     .unwrap();
+//!
+//! gameboy.press(Button::Start);
+//! gameboy.run_frame();
+//! let frame = gameboy.framebuffer();
+//! ```
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+use crate::cartridge::Cartridge;
+use crate::cpu::{self, Cpu, StepResult, CYCLES_PER_FRAME};
+use crate::joypad::Button;
+use crate::lcd::{self, Frame};
+use crate::memory::Mmu;
+use crate::opcodes::RegisterId::{A, B, C, D, E, H, L};
+use crate::subroutine::{self, Call, CallError};
+
+/// Clock frequency, in Hz
+pub const CLOCK_RATE: u64 = 4_194_304;
+
+/// Sample rate of `GameBoy::audio_samples`, in Hz
+pub const SAMPLE_RATE: u64 = 48_000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Model {
+    #[default]
+    Dmg,
+    /// Game Boy Color, as a stub: its additional hardware isn't emulated, only the value of A
+    /// after the boot ROM (0x11), which games check to detect it.
+    Cgb,
+}
+
+pub struct GameBoyBuilder {
+    cartridge: Cartridge,
+    model: Model,
+    boot_rom: Option<Vec<u8>>,
+    save_path: Option<PathBuf>,
+    rng_seed: Option<u64>,
+}
+
+impl GameBoyBuilder {
+    pub fn model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Starts from the given 256-byte boot ROM instead of the state it leaves the machine in.
+    /// `build` fails with `ErrorKind::InvalidInput` if it has another size.
+    pub fn boot_rom(mut self, boot_rom: Vec<u8>) -> Self {
+        self.boot_rom = Some(boot_rom);
+        self
+    }
+
+    /// File the cartridge RAM is loaded from, if it exists, and saved to by `GameBoy::save`.
+    pub fn save_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.save_path = Some(path.into());
+        self
+    }
+
+    /// Fills the RAM with pseudo-random values derived from `seed` at power-on, like on
+    /// hardware, instead of zeros.
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> io::Result<GameBoy> {
+        if let Some(boot_rom) = &self.boot_rom {
+            if boot_rom.len() != 0x100 {
+                return Err(io::Error::new(ErrorKind::InvalidInput,
+                                          format!("The boot ROM must be 256 bytes long, not {}", boot_rom.len())));
+            }
+        }
+
+        let (cpu, mut mmu) = power_on(&self);
+
+        if let Some(path) = &self.save_path {
+            match fs::read(path) {
+                Ok(save) => {
+                    let ram = mmu.external_ram_mut();
+                    let len = save.len().min(ram.len());
+                    ram[..len].copy_from_slice(&save[..len]);
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(GameBoy {
+            cpu,
+            mmu,
+            config: self,
+            frame: Frame::blank(),
+            samples: 0,
+        })
+    }
+}
+
+/// A Game Boy owning its CPU, memory, cartridge and peripherals.
+pub struct GameBoy {
+    cpu: Cpu,
+    mmu: Mmu,
+    /// Settings needed to power the machine on again on reset
+    config: GameBoyBuilder,
+    /// Screen at the end of the last frame
+    frame: Frame,
+    /// Number of audio samples returned since power-on
+    samples: u64,
+}
+
+impl GameBoy {
+    pub fn builder(cartridge: Cartridge) -> GameBoyBuilder {
+        GameBoyBuilder {
+            cartridge,
+            model: Model::Dmg,
+            boot_rom: None,
+            save_path: None,
+            rng_seed: None,
+        }
+    }
+
+    pub fn model(&self) -> Model {
+        self.config.model
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> StepResult {
+        self.cpu.step(&mut self.mmu)
+    }
+
+    /// Runs until the end of the current frame and renders the screen. Returns early, without
+    /// rendering, when a step doesn't return `StepResult::Continue`.
+    pub fn run_frame(&mut self) -> StepResult {
+        let end = (self.cpu.cycles() / CYCLES_PER_FRAME + 1) * CYCLES_PER_FRAME;
+
+        while self.cpu.cycles() < end {
+            let result = self.cpu.step(&mut self.mmu);
+            if result != StepResult::Continue {
+                return result;
+            }
+        }
+
+        self.frame = lcd::render(&self.mmu);
+        StepResult::Continue
+    }
+
+    /// Screen as rendered at the end of the last frame.
+    pub fn framebuffer(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// Audio produced since the last call, as interleaved stereo samples at `SAMPLE_RATE`.
+    /// This is a stub: sound isn't emulated yet, so the samples are silence, but their amount
+    /// follows the emulated time to keep audio output in sync.
+    pub fn audio_samples(&mut self) -> Vec<i16> {
+        let total = self.cpu.cycles() * SAMPLE_RATE / CLOCK_RATE;
+        let count = total - self.samples;
+        self.samples = total;
+
+        vec![0; 2 * count as usize]
+    }
+
+    pub fn press(&mut self, button: Button) {
+        self.mmu.set_button(button, true);
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.mmu.set_button(button, false);
+    }
+
+    /// Powers the machine off and on again. The cartridge RAM is kept, like on battery-backed
+    /// cartridges.
+    pub fn reset(&mut self) {
+        let ram = self.mmu.external_ram().to_vec();
+
+        (self.cpu, self.mmu) = power_on(&self.config);
+        self.mmu.external_ram_mut().copy_from_slice(&ram);
+        self.frame = Frame::blank();
+        self.samples = 0;
+    }
+
+    /// Writes the cartridge RAM to the save path, if there's one.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.config.save_path {
+            Some(path) => fs::write(path, self.mmu.external_ram()),
+            None => Ok(()),
+        }
+    }
+
+    /// Calls the routine at `address` and runs it until it returns. See `subroutine::call`.
+    pub fn call(&mut self, address: u16, max_cycles: u64) -> Result<Call, CallError> {
+        subroutine::call(&mut self.cpu, &mut self.mmu, address, max_cycles)
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    pub fn mmu(&self) -> &Mmu {
+        &self.mmu
+    }
+
+    pub fn mmu_mut(&mut self) -> &mut Mmu {
+        &mut self.mmu
+    }
+}
+
+fn power_on(config: &GameBoyBuilder) -> (Cpu, Mmu) {
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(config.cartridge.clone());
+
+    // Games wait for interrupts in such loops
+    cpu.set_stop_on_jr_loop(false);
+
+    if let Some(seed) = config.rng_seed {
+        randomize_ram(&mut mmu, seed);
+    }
+
+    match &config.boot_rom {
+        Some(boot_rom) => {
+            mmu.set_boot_rom(boot_rom.clone());
+
+            for reg in [A, B, C, D, E, H, L] {
+                cpu.regs[reg] = 0;
+            }
+            cpu.regs.flags.set_f(0);
+            cpu.regs.sp = 0;
+            cpu.regs.pc = 0;
+        }
+        None if config.model == Model::Cgb => cpu.regs[A] = 0x11,
+        None => {}
+    }
+
+    (cpu, mmu)
+}
+
+/// Fills work RAM and high RAM with the output of a SplitMix64 generator.
+fn randomize_ram(mmu: &mut Mmu, seed: u64) {
+    let mut state = seed;
+
+    for address in (0xC000..0xE000).chain(0xFF80..0xFFFF) {
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+        mmu[address] = (z ^ (z >> 31)) as u8;
+    }
+}
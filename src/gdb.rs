@@ -0,0 +1,387 @@
+//! Minimal GDB Remote Serial Protocol server.
+//!
+//! The SM83 register file is exposed as six 16-bit little-endian registers, in this order:
+//! AF, BC, DE, HL, SP, PC. Software breakpoints (`Z0`), write (`Z2`), read (`Z3`) and access
+//! (`Z4`) watchpoints, single-stepping and interrupting a running target with Ctrl-C are
+//! supported.
+
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::cpu::Cpu;
+use crate::debugger::{Debugger, StopReason, WatchKind};
+use crate::memory::{AccessKind, Mmu};
+use crate::opcodes::Register16Id;
+use crate::opcodes::RegisterId::{A, B, C, D, E, H, L};
+
+const REGISTERS: [Register16Id; 5] = [Register16Id::AF, Register16Id::BC, Register16Id::DE, Register16Id::HL, Register16Id::SP];
+
+/// Number of instructions executed between two checks for a Ctrl-C from the client.
+const INTERRUPT_CHECK_INTERVAL: u64 = 10_000;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+/// Accepts a single client on `listener` and serves it until it detaches, kills the target
+/// or disconnects.
+pub fn serve(listener: &TcpListener, cpu: &mut Cpu, mmu: &mut Mmu) -> io::Result<()> {
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+
+    GdbSession {
+        stream,
+        cpu,
+        mmu,
+        debugger: Debugger::new(),
+        exited: false,
+    }.run()
+}
+
+struct GdbSession<'a> {
+    stream: TcpStream,
+    cpu: &'a mut Cpu,
+    mmu: &'a mut Mmu,
+    debugger: Debugger,
+    exited: bool,
+}
+
+enum Reply {
+    Packet(String),
+    Close,
+}
+
+impl GdbSession<'_> {
+    fn run(&mut self) -> io::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            match self.handle(&packet) {
+                Reply::Packet(reply) => self.write_packet(&reply)?,
+                Reply::Close => return Ok(()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the next packet, acknowledging it. Returns `None` when the client disconnects.
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        let mut byte = [0u8];
+
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+
+            match byte[0] {
+                b'$' => break,
+                0x03 => return Ok(Some("?".to_owned())),
+                // acks, and anything received outside a packet
+                _ => continue,
+            }
+        }
+
+        let mut data = Vec::new();
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+
+        let mut checksum = [0u8; 2];
+        self.stream.read_exact(&mut checksum)?;
+
+        let expected = std::str::from_utf8(&checksum).ok().and_then(|c| u8::from_str_radix(c, 16).ok());
+        if expected != Some(checksum_of(&data)) {
+            self.stream.write_all(b"-")?;
+            return self.read_packet();
+        }
+
+        self.stream.write_all(b"+")?;
+        Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())?;
+
+        // wait for the acknowledgement, retransmitting on '-'
+        let mut byte = [0u8];
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(());
+            }
+            match byte[0] {
+                b'+' => return Ok(()),
+                b'-' => self.stream.write_all(packet.as_bytes())?,
+                _ => {}
+            }
+        }
+    }
+
+    fn handle(&mut self, packet: &str) -> Reply {
+        // Commands and their arguments are ASCII, and are sliced by byte below
+        if !packet.is_ascii() {
+            return Reply::Packet("E01".to_owned());
+        }
+        let (command, args) = packet.split_at(packet.len().min(1));
+
+        let reply = match command {
+            "?" => self.stop_reply(StopReason::Breakpoint(self.cpu.regs.pc), SIGTRAP),
+            "g" => self.read_registers(),
+            "G" => self.write_registers(args),
+            "p" => self.read_register(args),
+            "P" => self.write_register(args),
+            "m" => self.read_memory(args),
+            "M" => self.write_memory(args),
+            "c" => self.resume(args, false),
+            "s" => self.resume(args, true),
+            "Z" => self.set_breakpoint(args, true),
+            "z" => self.set_breakpoint(args, false),
+            "H" => "OK".to_owned(),
+            "q" => self.query(args),
+            "k" => return Reply::Close,
+            "D" => {
+                // a detach is acknowledged before closing
+                let _ = self.write_packet("OK");
+                return Reply::Close;
+            }
+            _ => "".to_owned(),
+        };
+
+        Reply::Packet(reply)
+    }
+
+    fn query(&self, args: &str) -> String {
+        if args.starts_with("Supported") {
+            "PacketSize=1000".to_owned()
+        } else if args == "Attached" {
+            "1".to_owned()
+        } else if args == "C" {
+            "QC1".to_owned()
+        } else {
+            "".to_owned()
+        }
+    }
+
+    fn register(&self, index: usize) -> Option<u16> {
+        match index {
+            0..=4 => Some(self.cpu.regs.get(REGISTERS[index])),
+            5 => Some(self.cpu.regs.pc),
+            _ => None,
+        }
+    }
+
+    fn set_register(&mut self, index: usize, value: u16) -> bool {
+        let regs = &mut self.cpu.regs;
+        let [hi, lo] = value.to_be_bytes();
+
+        match index {
+            0 => {
+                regs[A] = hi;
+                regs.flags.set_f(lo);
+            }
+            1 => [regs[B], regs[C]] = [hi, lo],
+            2 => [regs[D], regs[E]] = [hi, lo],
+            3 => [regs[H], regs[L]] = [hi, lo],
+            4 => regs.sp = value,
+            5 => regs.pc = value,
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn read_registers(&self) -> String {
+        (0..6).map(|i| hex_u16_le(self.register(i).unwrap())).collect()
+    }
+
+    fn write_registers(&mut self, args: &str) -> String {
+        if args.len() != 6 * 4 {
+            return "E01".to_owned();
+        }
+
+        for i in 0..6 {
+            match parse_u16_le(&args[i * 4..i * 4 + 4]) {
+                Some(value) => self.set_register(i, value),
+                None => return "E01".to_owned(),
+            };
+        }
+
+        "OK".to_owned()
+    }
+
+    fn read_register(&self, args: &str) -> String {
+        usize::from_str_radix(args, 16).ok()
+            .and_then(|i| self.register(i))
+            .map(hex_u16_le)
+            .unwrap_or_else(|| "E01".to_owned())
+    }
+
+    fn write_register(&mut self, args: &str) -> String {
+        let written = args.split_once('=')
+            .and_then(|(i, value)| Some((usize::from_str_radix(i, 16).ok()?, parse_u16_le(value)?)))
+            .is_some_and(|(i, value)| self.set_register(i, value));
+
+        if written { "OK" } else { "E01" }.to_owned()
+    }
+
+    fn read_memory(&self, args: &str) -> String {
+        match parse_address_length(args) {
+            Some((address, length)) => (0..length)
+                .map(|i| format!("{:02x}", self.mmu[address.wrapping_add(i)]))
+                .collect(),
+            None => "E01".to_owned(),
+        }
+    }
+
+    fn write_memory(&mut self, args: &str) -> String {
+        let parsed = args.split_once(':').and_then(|(range, data)| {
+            let (address, length) = parse_address_length(range)?;
+            let bytes = parse_bytes(data)?;
+            (bytes.len() == length as usize).then_some((address, bytes))
+        });
+
+        match parsed {
+            Some((address, bytes)) => {
+                for (i, byte) in bytes.into_iter().enumerate() {
+                    self.mmu[address.wrapping_add(i as u16)] = byte;
+                }
+                "OK".to_owned()
+            }
+            None => "E01".to_owned(),
+        }
+    }
+
+    fn set_breakpoint(&mut self, args: &str, insert: bool) -> String {
+        let mut parts = args.split(',');
+        let kind = parts.next();
+        let address = match parts.next().and_then(|a| u16::from_str_radix(a, 16).ok()) {
+            Some(address) => address,
+            None => return "E01".to_owned(),
+        };
+
+        let watch = match kind {
+            Some("0") | Some("1") => {
+                if insert {
+                    self.debugger.add_breakpoint(address);
+                } else {
+                    self.debugger.remove_breakpoint(address);
+                }
+                return "OK".to_owned();
+            }
+            Some("2") => WatchKind::Write,
+            Some("3") => WatchKind::Read,
+            Some("4") => WatchKind::ReadWrite,
+            _ => return "".to_owned(),
+        };
+
+        if insert {
+            self.debugger.add_watchpoint(address, watch);
+        } else {
+            self.debugger.remove_watchpoint(address);
+        }
+
+        "OK".to_owned()
+    }
+
+    fn resume(&mut self, args: &str, single_step: bool) -> String {
+        if self.exited {
+            return "W00".to_owned();
+        }
+        if let Ok(address) = u16::from_str_radix(args, 16) {
+            self.cpu.regs.pc = address;
+        }
+
+        if single_step {
+            let reason = self.debugger.step(self.cpu, self.mmu);
+            return self.stop_reply(reason, SIGTRAP);
+        }
+
+        loop {
+            let reason = self.debugger.resume(self.cpu, self.mmu, Some(INTERRUPT_CHECK_INTERVAL));
+
+            if reason != StopReason::Limit {
+                return self.stop_reply(reason, SIGTRAP);
+            }
+            if self.interrupted() {
+                return self.stop_reply(reason, SIGINT);
+            }
+        }
+    }
+
+    /// Checks, without blocking, whether the client sent a Ctrl-C.
+    fn interrupted(&mut self) -> bool {
+        let mut byte = [0u8];
+
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let received = match self.stream.peek(&mut byte) {
+            Ok(1) if byte[0] == 0x03 => self.stream.read(&mut byte).is_ok(),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => false,
+            _ => false,
+        };
+        let _ = self.stream.set_nonblocking(false);
+
+        received
+    }
+
+    fn stop_reply(&mut self, reason: StopReason, signal: u8) -> String {
+        match reason {
+            StopReason::Watchpoint { address, access, .. } => {
+                let kind = self.debugger.watchpoints().iter()
+                    .find(|w| w.address == address)
+                    .map(|w| w.kind);
+                let name = match (kind, access) {
+                    (Some(WatchKind::ReadWrite), _) => "awatch",
+                    (_, AccessKind::Read) => "rwatch",
+                    (_, _) => "watch",
+                };
+                format!("T{:02x}{}:{:04x};", SIGTRAP, name, address)
+            }
+            StopReason::Stopped => {
+                self.exited = true;
+                "W00".to_owned()
+            }
+            StopReason::IllegalOpcode { .. } => format!("S{:02x}", SIGILL),
+            _ => format!("S{:02x}", signal),
+        }
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+fn hex_u16_le(value: u16) -> String {
+    let [lo, hi] = value.to_le_bytes();
+    format!("{:02x}{:02x}", lo, hi)
+}
+
+fn parse_u16_le(hex: &str) -> Option<u16> {
+    match parse_bytes(hex)?.as_slice() {
+        [lo, hi] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => None,
+    }
+}
+
+fn parse_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_address_length(args: &str) -> Option<(u16, u16)> {
+    let (address, length) = args.split_once(',')?;
+
+    Some((u16::from_str_radix(address, 16).ok()?, u16::from_str_radix(length, 16).ok()?))
+}
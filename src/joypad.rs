@@ -0,0 +1,70 @@
+//! Joypad, read through the P1 register (0xFF00).
+//!
+//! Writing bit 4 or 5 of P1 to 0 selects the direction keys or the buttons, and the low
+//! nibble then reads the selected keys, 0 meaning pressed.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    /// Bit of the button in the low nibble of P1, and the select bit it's read through.
+    fn line(self) -> (u8, u8) {
+        match self {
+            Button::Right => (0x01, 0x10),
+            Button::Left => (0x02, 0x10),
+            Button::Up => (0x04, 0x10),
+            Button::Down => (0x08, 0x10),
+            Button::A => (0x01, 0x20),
+            Button::B => (0x02, 0x20),
+            Button::Select => (0x04, 0x20),
+            Button::Start => (0x08, 0x20),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Joypad {
+    /// Pressed direction keys, as P1 bits
+    directions: u8,
+    /// Pressed buttons, as P1 bits
+    buttons: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        Joypad::default()
+    }
+
+    pub fn set(&mut self, button: Button, pressed: bool) {
+        let (bit, select) = button.line();
+        let keys = if select == 0x10 { &mut self.directions } else { &mut self.buttons };
+
+        if pressed {
+            *keys |= bit;
+        } else {
+            *keys &= !bit;
+        }
+    }
+
+    /// Value read from P1, given the select bits last written to it.
+    pub fn p1(&self, written: u8) -> u8 {
+        let mut pressed = 0;
+        if written & 0x10 == 0 {
+            pressed |= self.directions;
+        }
+        if written & 0x20 == 0 {
+            pressed |= self.buttons;
+        }
+
+        0xC0 | written & 0x30 | !pressed & 0x0F
+    }
+}
@@ -0,0 +1,184 @@
+use crate::memory::Mmu;
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+const LCDC: u16 = 0xFF40;
+const SCY: u16 = 0xFF42;
+const SCX: u16 = 0xFF43;
+const BGP: u16 = 0xFF47;
+const OBP0: u16 = 0xFF48;
+const OBP1: u16 = 0xFF49;
+const WY: u16 = 0xFF4A;
+const WX: u16 = 0xFF4B;
+
+const MAX_SPRITES_PER_LINE: usize = 10;
+
+/// An image of the LCD, holding one shade (0 = lightest to 3 = darkest) per pixel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// A frame as displayed when the LCD is off.
+    pub fn blank() -> Frame {
+        Frame {
+            pixels: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+        }
+    }
+
+    /// Builds a frame from shades given row by row.
+    pub fn from_pixels(pixels: Vec<u8>) -> Frame {
+        assert_eq!(SCREEN_WIDTH * SCREEN_HEIGHT, pixels.len(), "Invalid frame size");
+        assert!(pixels.iter().all(|&shade| shade < 4), "Invalid shade");
+
+        Frame { pixels }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * SCREEN_WIDTH + x]
+    }
+
+    /// Shades of all pixels, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+/// Renders the whole screen from the current content of VRAM, OAM and the LCD registers.
+///
+/// Every line is drawn with the same register values, so effects relying on changing them
+/// mid-frame are not reproduced.
+pub fn render(mmu: &Mmu) -> Frame {
+    let mut frame = Frame::blank();
+    let lcdc = mmu[LCDC];
+
+    if lcdc & 0x80 == 0 {
+        return frame;
+    }
+
+    for y in 0..SCREEN_HEIGHT {
+        let line = &mut frame.pixels[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH];
+        // Colors before palette mapping, needed to resolve sprite priority
+        let mut bg_colors = [0u8; SCREEN_WIDTH];
+
+        if lcdc & 0x01 != 0 {
+            render_background(mmu, y as u8, &mut bg_colors);
+
+            if lcdc & 0x20 != 0 {
+                render_window(mmu, y as u8, &mut bg_colors);
+            }
+        }
+
+        for (pixel, &color) in line.iter_mut().zip(bg_colors.iter()) {
+            *pixel = shade(mmu[BGP], color);
+        }
+
+        if lcdc & 0x02 != 0 {
+            render_sprites(mmu, y as u8, &bg_colors, line);
+        }
+    }
+
+    frame
+}
+
+fn render_background(mmu: &Mmu, y: u8, colors: &mut [u8; SCREEN_WIDTH]) {
+    let map = if mmu[LCDC] & 0x08 != 0 { 0x9C00 } else { 0x9800 };
+    let map_y = y.wrapping_add(mmu[SCY]);
+
+    for (x, color) in colors.iter_mut().enumerate() {
+        let map_x = (x as u8).wrapping_add(mmu[SCX]);
+        *color = tile_map_color(mmu, map, map_x, map_y);
+    }
+}
+
+fn render_window(mmu: &Mmu, y: u8, colors: &mut [u8; SCREEN_WIDTH]) {
+    let (wx, wy) = (mmu[WX] as usize, mmu[WY]);
+    if y < wy || wx > 166 {
+        return;
+    }
+
+    let map = if mmu[LCDC] & 0x40 != 0 { 0x9C00 } else { 0x9800 };
+
+    // The window is drawn from WX - 7
+    for (x, color) in colors.iter_mut().enumerate().skip(wx.saturating_sub(7)) {
+        *color = tile_map_color(mmu, map, (x + 7 - wx) as u8, y - wy);
+    }
+}
+
+fn tile_map_color(mmu: &Mmu, map: u16, x: u8, y: u8) -> u8 {
+    let tile = mmu[map + (y as u16 / 8) * 32 + x as u16 / 8];
+
+    let address = if mmu[LCDC] & 0x10 != 0 {
+        0x8000 + tile as u16 * 16
+    } else {
+        (0x9000 + (tile as i8 as i32) * 16) as u16
+    };
+
+    tile_color(mmu, address, x % 8, y % 8)
+}
+
+/// Color index (0-3) of a pixel of the tile stored at `address`.
+fn tile_color(mmu: &Mmu, address: u16, x: u8, y: u8) -> u8 {
+    let low = mmu[address + y as u16 * 2];
+    let high = mmu[address + y as u16 * 2 + 1];
+    let bit = 7 - x;
+
+    ((high >> bit) & 1) << 1 | ((low >> bit) & 1)
+}
+
+fn render_sprites(mmu: &Mmu, y: u8, bg_colors: &[u8; SCREEN_WIDTH], line: &mut [u8]) {
+    let height = if mmu[LCDC] & 0x04 != 0 { 16 } else { 8 };
+
+    let mut sprites: Vec<u16> = (0..40u16)
+        .map(|i| 0xFE00 + i * 4)
+        .filter(|&entry| {
+            let top = mmu[entry] as i16 - 16;
+            (top..top + height).contains(&(y as i16))
+        })
+        .take(MAX_SPRITES_PER_LINE)
+        .collect();
+
+    // Sprites with a lower X are drawn on top, then the ones coming first in OAM. Drawing
+    // in reverse order lets the sprite with the highest priority be drawn last.
+    sprites.sort_by_key(|&entry| mmu[entry + 1]);
+
+    for &entry in sprites.iter().rev() {
+        let (sprite_y, sprite_x) = (mmu[entry] as i16 - 16, mmu[entry + 1] as i16 - 8);
+        let attributes = mmu[entry + 3];
+
+        let mut row = y as i16 - sprite_y;
+        if attributes & 0x40 != 0 {
+            row = height - 1 - row;
+        }
+
+        let mut tile = mmu[entry + 2];
+        if height == 16 {
+            tile &= 0xFE;
+        }
+        let address = 0x8000 + tile as u16 * 16;
+        let palette = if attributes & 0x10 != 0 { mmu[OBP1] } else { mmu[OBP0] };
+
+        for column in 0..8 {
+            let x = sprite_x + column;
+            if !(0..SCREEN_WIDTH as i16).contains(&x) {
+                continue;
+            }
+
+            let tile_x = if attributes & 0x20 != 0 { 7 - column } else { column };
+            let color = tile_color(mmu, address, tile_x as u8, row as u8);
+
+            // Color 0 is transparent, and the background can have priority over the sprite
+            if color == 0 || (attributes & 0x80 != 0 && bg_colors[x as usize] != 0) {
+                continue;
+            }
+
+            line[x as usize] = shade(palette, color);
+        }
+    }
+}
+
+fn shade(palette: u8, color: u8) -> u8 {
+    (palette >> (color * 2)) & 0x03
+}
@@ -1,4 +1,18 @@
 pub mod cpu;
 pub mod memory;
 pub mod opcodes;
-pub mod cartridge;
\ No newline at end of file
+pub mod cartridge;
+pub mod trace;
+pub mod debugger;
+pub mod gdb;
+pub mod lcd;
+pub mod screenshot;
+pub mod regression;
+pub mod testrom;
+pub mod alu;
+pub mod timer;
+pub mod joypad;
+pub mod gameboy;
+pub mod bus;
+pub mod subroutine;
+pub mod assembler;
\ No newline at end of file
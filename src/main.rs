@@ -0,0 +1,219 @@
+//! Headless ROM runner.
+//!
+//! Exit codes: 0 when the run completed, 1 on emulator errors or failed test ROMs, 2 on
+//! invalid arguments or unreadable ROMs.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::{env, process};
+
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::cpu::{Cpu, IllegalOpcodePolicy, StepResult, CYCLES_PER_FRAME};
+use ruboy::debugger::format_registers;
+use ruboy::lcd;
+use ruboy::memory::Mmu;
+use ruboy::screenshot::{self, Palette};
+use ruboy::testrom::{blargg_result, mooneye_result, TestResult, MOONEYE_BREAKPOINT};
+
+const USAGE: &str = "\
+Usage: ruboy <rom> [options]
+
+Options:
+  --frames <n>           stop after n frames
+  --cycles <n>           stop after n clock cycles
+  --until-pc <addr>      stop when PC reaches addr
+  --until-serial <text>  stop when the serial output contains text
+  --break-opcode <op>    stop after executing the given opcode
+  --illegal-opcode <p>   on illegal opcodes: error (default), lock like the hardware,
+                         or trap like a breakpoint
+  --mooneye              stop at LD B,B and check the Mooneye result registers
+  --blargg               check the blargg result in the serial output or at $A000
+  --serial               print the serial output
+  --regs                 print the final registers
+  --dump <addr>:<len>    hexdump a memory range (can be repeated)
+  --screenshot <file>    save the final screen as a PNG image
+  --palette <palette>    screenshot colors: grayscale (default), green, or four
+                         comma-separated RRGGBB colors from lightest to darkest
+
+Addresses and lengths are hexadecimal, optionally prefixed with $ or 0x.";
+
+#[derive(Default)]
+struct Options {
+    rom: String,
+    max_cycles: Option<u64>,
+    until_pc: Option<u16>,
+    until_serial: Option<String>,
+    breakpoint_opcode: Option<u8>,
+    illegal_opcode_policy: Option<IllegalOpcodePolicy>,
+    mooneye: bool,
+    blargg: bool,
+    print_serial: bool,
+    print_registers: bool,
+    dumps: Vec<(u16, u16)>,
+    screenshot: Option<String>,
+    palette: Option<Palette>,
+}
+
+fn parse_hex(arg: &str) -> Option<u16> {
+    u16::from_str_radix(arg.trim_start_matches("0x").trim_start_matches('$'), 16).ok()
+}
+
+fn parse_options(args: &[String]) -> Option<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => options.max_cycles = Some(args.next()?.parse::<u64>().ok()?.checked_mul(CYCLES_PER_FRAME)?),
+            "--cycles" => options.max_cycles = Some(args.next()?.parse().ok()?),
+            "--until-pc" => options.until_pc = Some(parse_hex(args.next()?)?),
+            "--until-serial" => options.until_serial = Some(args.next()?.clone()),
+            "--break-opcode" => options.breakpoint_opcode = Some(parse_hex(args.next()?)?.try_into().ok()?),
+            "--illegal-opcode" => options.illegal_opcode_policy = Some(args.next()?.parse().ok()?),
+            "--mooneye" => {
+                options.mooneye = true;
+                options.breakpoint_opcode = Some(MOONEYE_BREAKPOINT);
+            }
+            "--blargg" => options.blargg = true,
+            "--serial" => options.print_serial = true,
+            "--regs" => options.print_registers = true,
+            "--dump" => {
+                let (address, len) = args.next()?.split_once(':')?;
+                options.dumps.push((parse_hex(address)?, parse_hex(len)?));
+            }
+            "--screenshot" => options.screenshot = Some(args.next()?.clone()),
+            "--palette" => options.palette = Some(args.next()?.parse().ok()?),
+            _ if arg.starts_with("--") || !options.rom.is_empty() => return None,
+            _ => options.rom = arg.clone(),
+        }
+    }
+
+    (!options.rom.is_empty()).then_some(options)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum StopCondition {
+    CycleLimit,
+    Pc,
+    Serial,
+    Stopped,
+    Breakpoint,
+    IllegalOpcode,
+}
+
+impl StopCondition {
+    fn description(self) -> &'static str {
+        match self {
+            StopCondition::CycleLimit => "cycle limit reached",
+            StopCondition::Pc => "PC reached",
+            StopCondition::Serial => "serial output matched",
+            StopCondition::Stopped => "program stopped",
+            StopCondition::Breakpoint => "breakpoint opcode executed",
+            StopCondition::IllegalOpcode => "illegal opcode reached",
+        }
+    }
+}
+
+/// Runs until the program stops or one of the stop conditions is met, and returns why it stopped.
+fn run(cpu: &mut Cpu, mmu: &mut Mmu, options: &Options) -> StopCondition {
+    cpu.set_breakpoint_opcode(options.breakpoint_opcode);
+    cpu.set_illegal_opcode_policy(options.illegal_opcode_policy.unwrap_or(IllegalOpcodePolicy::Error));
+
+    loop {
+        if options.max_cycles.is_some_and(|max| cpu.cycles() >= max) {
+            return StopCondition::CycleLimit;
+        }
+        if options.until_pc == Some(cpu.regs.pc) {
+            return StopCondition::Pc;
+        }
+        if let Some(text) = &options.until_serial {
+            if String::from_utf8_lossy(mmu.serial_output()).contains(text.as_str()) {
+                return StopCondition::Serial;
+            }
+        }
+
+        match cpu.step(mmu) {
+            StepResult::Continue => {}
+            StepResult::Stopped => return StopCondition::Stopped,
+            StepResult::Breakpoint => return StopCondition::Breakpoint,
+            StepResult::IllegalOpcode { .. } => return StopCondition::IllegalOpcode,
+        }
+    }
+}
+
+fn hexdump(mmu: &Mmu, address: u16, len: u16) {
+    for row in (0..len).step_by(16) {
+        let start = address.wrapping_add(row);
+        let bytes: Vec<String> = (0..16.min(len - row))
+            .map(|i| format!("{:02X}", mmu[start.wrapping_add(i)]))
+            .collect();
+
+        println!("{:04X}  {}", start, bytes.join(" "));
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let cartridge = Cartridge::load(&options.rom).unwrap_or_else(|e| {
+        eprintln!("Couldn't read {}: {}", options.rom, e);
+        process::exit(2);
+    });
+
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    // The panic message is printed by the default hook
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run(&mut cpu, &mut mmu, &options)));
+
+    if options.print_serial {
+        println!("{}", String::from_utf8_lossy(mmu.serial_output()));
+    }
+    if options.print_registers {
+        println!("{}", format_registers(&cpu));
+    }
+    for (address, len) in &options.dumps {
+        hexdump(&mmu, *address, *len);
+    }
+    if let Some(path) = &options.screenshot {
+        let palette = options.palette.unwrap_or(Palette::Grayscale);
+        if let Err(e) = screenshot::save_png(path, &lcd::render(&mmu), palette) {
+            eprintln!("Couldn't write {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+
+    let condition = match result {
+        Ok(StopCondition::IllegalOpcode) => {
+            eprintln!("Illegal opcode {:#04x} at PC={:#06x} after {} cycles", mmu[cpu.regs.pc], cpu.regs.pc, cpu.cycles());
+            process::exit(1);
+        }
+        Ok(condition) => condition,
+        Err(_) => {
+            eprintln!("Emulator error at PC={:#06x} after {} cycles", cpu.regs.pc, cpu.cycles());
+            process::exit(1);
+        }
+    };
+
+    eprintln!("Stopped after {} cycles: {}", cpu.cycles(), condition.description());
+
+    let result = if options.mooneye {
+        match condition {
+            StopCondition::Breakpoint => mooneye_result(&cpu.regs),
+            _ => TestResult::Unfinished,
+        }
+    } else if options.blargg {
+        blargg_result(&mmu)
+    } else {
+        return;
+    };
+
+    println!("Result: {:?}", result);
+    if result != TestResult::Passed {
+        process::exit(1);
+    }
+}
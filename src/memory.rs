@@ -1,25 +1,82 @@
-use std::ops::{Index, IndexMut};
+use std::ops::{Index, IndexMut, RangeInclusive};
 
+use crate::bus::Bus;
 use crate::cartridge::Cartridge;
+use crate::joypad::{Button, Joypad};
+use crate::timer::Timer;
 
+/// Memory accesses done through `read`, `write` and `fetch` can be observed by hooks.
+/// Accesses done through the `Index`/`IndexMut` operators are not, which lets tools peek
+/// and poke memory without triggering them.
 pub struct Mmu {
     internal_ram: Vec<u8>,
     io_ports: Vec<u8>,
     internal_8kb_ram: Vec<u8>,
     switchable_ram: Vec<u8>,
     video_ram: Vec<u8>,
+    oam: Vec<u8>,
+    /// Scratch byte handed out by `IndexMut` for the unusable area (0xFEA0-0xFEFF) and past
+    /// the end of the cartridge. It's reset to 0xFF on each access, so writes are ignored.
+    unusable: u8,
     cartridge: Cartridge,
+    /// Bytes sent through the serial port
+    serial_output: Vec<u8>,
+    timer: Timer,
+    joypad: Joypad,
+    /// Boot ROM mapped over 0x0000-0x00FF, until it's disabled by writing to 0xFF50
+    boot_rom: Option<Vec<u8>>,
+    hooks: Vec<Hook>,
+    next_hook_id: usize,
+    /// Address of the instruction being executed
+    pc: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    /// Fetch of an opcode
+    Execute,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    pub address: u16,
+    /// Value read, written or fetched
+    pub value: u8,
+    /// Address of the instruction performing the access
+    pub pc: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HookId(usize);
+
+struct Hook {
+    id: HookId,
+    kind: AccessKind,
+    range: RangeInclusive<u16>,
+    callback: Box<dyn FnMut(&MemoryAccess)>,
 }
 
 impl Mmu {
     pub fn new(cart: Cartridge) -> Mmu {
         let mut mmu = Mmu {
             internal_ram: vec![0; 0xFFFF - 0xFF80 + 1],
-            io_ports: vec![0; 0xFF4C - 0xFF00],
+            io_ports: vec![0; 0xFF80 - 0xFF00],
             internal_8kb_ram: vec![0; 0xE000 - 0xC000],
             switchable_ram: vec![0; 0xC000 - 0xA000],
             video_ram: vec![0; 0xA000 - 0x8000],
+            oam: vec![0; 0xFEA0 - 0xFE00],
+            unusable: 0xFF,
             cartridge: cart,
+            serial_output: Vec::new(),
+            timer: Timer::new(0x18),
+            joypad: Joypad::new(),
+            boot_rom: None,
+            hooks: Vec::new(),
+            next_hook_id: 0,
+            pc: 0,
         };
 
         Self::init_io_ports(&mut mmu);
@@ -27,6 +84,138 @@ impl Mmu {
         mmu
     }
 
+    /// Registers a callback invoked on every access of the given kind to an address in `range`.
+    pub fn add_hook<F>(&mut self, kind: AccessKind, range: RangeInclusive<u16>, callback: F) -> HookId
+    where
+        F: FnMut(&MemoryAccess) + 'static,
+    {
+        let id = HookId(self.next_hook_id);
+        self.next_hook_id += 1;
+
+        self.hooks.push(Hook {
+            id,
+            kind,
+            range,
+            callback: Box::new(callback),
+        });
+
+        id
+    }
+
+    pub fn remove_hook(&mut self, id: HookId) -> bool {
+        let len = self.hooks.len();
+        self.hooks.retain(|hook| hook.id != id);
+        self.hooks.len() != len
+    }
+
+    /// Sets the address of the instruction being executed, reported to hooks.
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    pub fn read(&mut self, address: u16) -> u8 {
+        let value = self[address];
+        self.notify(AccessKind::Read, address, value);
+        value
+    }
+
+    /// Writes to the ROM area are ignored, since cartridges have no mapper yet. Use the
+    /// `IndexMut` operator to patch the ROM.
+    pub fn write(&mut self, address: u16, value: u8) {
+        if address >= 0x8000 {
+            self[address] = value;
+        }
+
+        match address {
+            0xFF00 => self[0xFF00] = self.joypad.p1(value),
+            0xFF04 => self.timer.reset(&mut self.io_ports),
+            0xFF50 if value != 0 => self.boot_rom = None,
+            _ => {}
+        }
+
+        if address == 0xFF02 && value & 0x81 == 0x81 {
+            self.transfer_serial();
+        }
+
+        self.notify(AccessKind::Write, address, value);
+    }
+
+    /// Advances the hardware clocked alongside the CPU by the given number of clock cycles.
+    pub fn tick(&mut self, cycles: u8) {
+        self.timer.tick(&mut self.io_ports, cycles);
+    }
+
+    /// Presses or releases a button, requesting the joypad interrupt when one of the selected
+    /// lines of P1 goes low.
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        let before = self[0xFF00];
+        self.joypad.set(button, pressed);
+        self[0xFF00] = self.joypad.p1(before);
+
+        if before & !self[0xFF00] & 0x0F != 0 {
+            self[0xFF0F] |= 0x10;
+        }
+    }
+
+    /// Maps a 256-byte boot ROM over the start of the cartridge.
+    pub fn set_boot_rom(&mut self, boot_rom: Vec<u8>) {
+        assert_eq!(0x100, boot_rom.len(), "The boot ROM must be 256 bytes long");
+        self.boot_rom = Some(boot_rom);
+    }
+
+    /// Cartridge RAM (0xA000-0xBFFF), kept by battery-backed cartridges.
+    pub fn external_ram(&self) -> &[u8] {
+        &self.switchable_ram
+    }
+
+    pub fn external_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.switchable_ram
+    }
+
+    /// Resets the DIV counter like a write to DIV, without notifying hooks.
+    pub fn reset_div(&mut self) {
+        self.timer.reset(&mut self.io_ports);
+    }
+
+    /// Bytes sent through the serial port since the MMU was created.
+    pub fn serial_output(&self) -> &[u8] {
+        &self.serial_output
+    }
+
+    /// Completes a transfer started with the internal clock immediately. No link cable is
+    /// connected, so the received byte is always 0xFF.
+    fn transfer_serial(&mut self) {
+        self.serial_output.push(self[0xFF01]);
+        self[0xFF01] = 0xFF;
+        self[0xFF02] &= 0x7F;
+    }
+
+    /// Reads an opcode byte.
+    pub fn fetch(&mut self, address: u16) -> u8 {
+        let value = self[address];
+        self.notify(AccessKind::Execute, address, value);
+        value
+    }
+
+    fn notify(&mut self, kind: AccessKind, address: u16, value: u8) {
+        if self.hooks.is_empty() {
+            return;
+        }
+
+        let access = MemoryAccess {
+            kind,
+            address,
+            value,
+            pc: self.pc,
+        };
+
+        for hook in self.hooks.iter_mut() {
+            if hook.kind == kind && hook.range.contains(&address) {
+                (hook.callback)(&access);
+            }
+        }
+    }
+
     fn init_io_ports(mmu: &mut Mmu) {
         mmu[0xFF00] = 0xCF;
         mmu[0xFF01] = 0x00;
@@ -65,12 +254,51 @@ impl Mmu {
     }
 }
 
+impl Bus for Mmu {
+    fn read(&mut self, address: u16) -> u8 {
+        Mmu::read(self, address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        Mmu::write(self, address, value)
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        Mmu::tick(self, cycles)
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self[address]
+    }
+
+    fn poke(&mut self, address: u16, value: u8) {
+        self[address] = value;
+    }
+
+    fn fetch(&mut self, address: u16) -> u8 {
+        Mmu::fetch(self, address)
+    }
+
+    fn set_pc(&mut self, pc: u16) {
+        Mmu::set_pc(self, pc)
+    }
+
+    fn reset_div(&mut self) {
+        Mmu::reset_div(self)
+    }
+}
+
 impl Index<u16> for Mmu {
     type Output = u8;
 
     fn index(&self, index: u16) -> &Self::Output {
+        if let (0x0000..=0x00FF, Some(boot_rom)) = (index, &self.boot_rom) {
+            return &boot_rom[index as usize];
+        }
+
         if index < 0x8000 {
-            return &self.cartridge.content[index as usize];
+            // Cartridges smaller than 32 KiB leave the bus floating
+            return self.cartridge.content.get(index as usize).unwrap_or(&0xFF);
         } else if index < 0xA000 {
             return &self.video_ram[(index - 0x8000) as usize];
         } else if index < 0xC000 {
@@ -80,8 +308,12 @@ impl Index<u16> for Mmu {
         } else if index < 0xFE00 {
             // echo of internal RAM
             return &self.internal_8kb_ram[(index - 0xE000) as usize];
+        } else if index < 0xFEA0 {
+            return &self.oam[(index - 0xFE00) as usize];
+        } else if index < 0xFF00 {
+            return &0xFF;
         }
-        if index >= 0xFF00 && index < 0xFF4C {
+        if (0xFF00..0xFF80).contains(&index) {
             return &self.io_ports[(index - 0xFF00) as usize];
         }
         if index >= 0xFF80 {
@@ -95,7 +327,13 @@ impl Index<u16> for Mmu {
 impl IndexMut<u16> for Mmu {
     fn index_mut(&mut self, index: u16) -> &mut Self::Output {
         if index < 0x8000 {
-            return &mut self.cartridge.content[index as usize];
+            return match self.cartridge.content.get_mut(index as usize) {
+                Some(byte) => byte,
+                None => {
+                    self.unusable = 0xFF;
+                    &mut self.unusable
+                }
+            };
         } else if index < 0xA000 {
             return &mut self.video_ram[(index - 0x8000) as usize];
         } else if index < 0xC000 {
@@ -105,8 +343,13 @@ impl IndexMut<u16> for Mmu {
         } else if index < 0xFE00 {
             // echo of internal RAM
             return &mut self.internal_8kb_ram[(index - 0xE000) as usize];
+        } else if index < 0xFEA0 {
+            return &mut self.oam[(index - 0xFE00) as usize];
+        } else if index < 0xFF00 {
+            self.unusable = 0xFF;
+            return &mut self.unusable;
         }
-        if index >= 0xFF00 && index < 0xFF4C {
+        if (0xFF00..0xFF80).contains(&index) {
             return &mut self.io_ports[(index - 0xFF00) as usize];
         }
         if index >= 0xFF80 {
@@ -1,3 +1,5 @@
+use std::sync::OnceLock;
+
 use InstructionType::*;
 
 use crate::opcodes::Operand::{Byte, DirectAddress, IndirectAddress, Register, Register16, Flag, Value, IoPort, SpOffset, IoPortOffset};
@@ -12,7 +14,7 @@ pub struct Instruction {
     pub cycles: u8,
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum InstructionType {
     NOP,
 
@@ -74,7 +76,7 @@ pub enum InstructionType {
     DI,
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub enum Operand {
     Byte,
     Register(RegisterId),
@@ -112,7 +114,7 @@ pub enum Register16Id {
     SP,
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub enum FlagId {
     Z,
     NZ,
@@ -120,6 +122,120 @@ pub enum FlagId {
     C,
 }
 
+impl Instruction {
+    /// Whether the opcode is encoded behind the 0xCB prefix.
+    pub fn is_prefixed(&self) -> bool {
+        matches!(self.kind, RLC | RRC | RL | RR | SLA | SRA | SWAP | SRL | BIT | RES | SET)
+    }
+
+    /// Number of bytes used by the opcode itself (1, or 2 for 0xCB xx).
+    pub fn opcode_size(&self) -> u8 {
+        if self.is_prefixed() { 2 } else { 1 }
+    }
+
+    /// Number of immediate bytes (d8, d16, a8, a16, r8) following the opcode.
+    pub fn immediate_size(&self) -> u8 {
+        match self.kind {
+            // STOP is followed by a padding byte
+            STOP => 1,
+            _ => self.operand_size(&self.lhs) + self.operand_size(&self.rhs),
+        }
+    }
+
+    /// Total encoded length of the instruction in bytes.
+    pub fn length(&self) -> u8 {
+        self.opcode_size() + self.immediate_size()
+    }
+
+    /// Whether the instruction depends on a flag (JR NZ, CALL C, RET Z etc).
+    pub fn is_conditional(&self) -> bool {
+        matches!(self.lhs, Some(Flag(_)))
+    }
+
+    /// Number of clock cycles taken when the condition of a conditional branch is met. Equal to
+    /// `cycles` for all other instructions.
+    pub fn cycles_taken(&self) -> u8 {
+        if !self.is_conditional() {
+            return self.cycles;
+        }
+
+        match self.kind {
+            JR | JP => self.cycles + 4,
+            CALL | RET => self.cycles + 12,
+            _ => self.cycles,
+        }
+    }
+
+    /// Whether the instruction may transfer control somewhere else than the next instruction.
+    pub fn is_branch(&self) -> bool {
+        matches!(self.kind, JR | JP | CALL | RET | RETI | RST)
+    }
+
+    fn operand_size(&self, op: &Option<Operand>) -> u8 {
+        match op {
+            Some(Byte) => match self.kind {
+                LD16 | JP | CALL => 2,
+                _ => 1,
+            },
+            Some(DirectAddress) => 2,
+            Some(IoPortOffset) | Some(SpOffset) => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// Disassembles the instruction encoded at the beginning of `bytes`, replacing immediate
+/// placeholders in the mnemonic with their actual values. Returns `None` if the opcode is
+/// unused or if `bytes` is too short to hold the whole instruction.
+pub fn disassemble(bytes: &[u8]) -> Option<String> {
+    let instr = decode(*bytes.first()?, *bytes.get(1).unwrap_or(&0))?;
+    let start = instr.opcode_size() as usize;
+    let immediate = bytes.get(start..instr.length() as usize)?;
+
+    let text = match (instr.kind, immediate) {
+        (STOP, _) => instr.mnemonic.to_owned(),
+        (_, [n]) => {
+            if instr.mnemonic.contains("+r8") {
+                instr.mnemonic.replace("+r8", &format!("{:+}", *n as i8))
+            } else if instr.mnemonic.contains("r8") {
+                instr.mnemonic.replace("r8", &format!("{:+}", *n as i8))
+            } else {
+                instr.mnemonic.replace("d8", &format!("${:02X}", n)).replace("a8", &format!("${:02X}", n))
+            }
+        }
+        (_, [lo, hi]) => {
+            let nn = u16::from_le_bytes([*lo, *hi]);
+            instr.mnemonic.replace("d16", &format!("${:04X}", nn)).replace("a16", &format!("${:04X}", nn))
+        }
+        _ => instr.mnemonic.to_owned(),
+    };
+
+    Some(text)
+}
+
+/// Decoded instructions, indexed by opcode, then by the byte following 0xCB for prefixed ones
+struct InstructionTable {
+    unprefixed: [Option<Instruction>; 256],
+    prefixed: [Option<Instruction>; 256],
+}
+
+static INSTRUCTIONS: OnceLock<InstructionTable> = OnceLock::new();
+
+/// Looks up an instruction in a table built on first use. `cb_opcode` is only used when
+/// `opcode` is the 0xCB prefix.
+pub fn decode(opcode: u8, cb_opcode: u8) -> Option<&'static Instruction> {
+    let table = INSTRUCTIONS.get_or_init(|| InstructionTable {
+        unprefixed: std::array::from_fn(|opcode| Instruction::try_from((opcode as u8, 0x00)).ok()),
+        prefixed: std::array::from_fn(|opcode| try_from_cb(opcode as u8).ok()),
+    });
+
+    if opcode == 0xCB {
+        table.prefixed[cb_opcode as usize].as_ref()
+    } else {
+        table.unprefixed[opcode as usize].as_ref()
+    }
+}
+
 impl TryFrom<(u8, u8)> for Instruction {
     type Error = ();
 
@@ -257,7 +373,7 @@ impl TryFrom<(u8, u8)> for Instruction {
             0x73 => Ok(instr2("LD (HL),E", LD, IndirectAddress(HL), Register(RegisterId::E), 8)),
             0x74 => Ok(instr2("LD (HL),H", LD, IndirectAddress(HL), Register(RegisterId::H), 8)),
             0x75 => Ok(instr2("LD (HL),L", LD, IndirectAddress(HL), Register(RegisterId::L), 8)),
-            0x76 => todo!(),
+            0x76 => Ok(instr0("HALT", HALT, 4)),
             0x77 => Ok(instr2("LD (HL),A", LD, IndirectAddress(HL), Register(RegisterId::A), 8)),
 
             0x78 => Ok(instr2("LD A,B", LD, Register(RegisterId::A), Register(RegisterId::B), 4)),
@@ -345,25 +461,25 @@ impl TryFrom<(u8, u8)> for Instruction {
             0xC1 => Ok(instr1("POP BC", POP, Register16(Register16Id::BC), 12)),
             0xC2 => Ok(instr2("JP NZ,a16", JP, Flag(FlagId::NZ), Byte, 12)),
             0xC3 => Ok(instr1("JP a16", JP, Byte, 16)),
-            0xC4 => Ok(instr2("CALL NZ,a16", CALL, Flag(FlagId::NZ), Byte, 16)),
+            0xC4 => Ok(instr2("CALL NZ,a16", CALL, Flag(FlagId::NZ), Byte, 12)),
             0xC5 => Ok(instr1("PUSH BC", PUSH, Register16(Register16Id::BC), 16)),
-            0xC6 => Ok(instr1("ADD A,a8", ADD, Byte, 8)),
+            0xC6 => Ok(instr1("ADD A,d8", ADD, Byte, 8)),
             0xC7 => Ok(instr1("RST 00H", RST, Value(0x00), 16)),
 
             0xC8 => Ok(instr1("RET Z", RET, Flag(FlagId::Z), 8)),
             0xC9 => Ok(instr0("RET", RET, 16)),
             0xCA => Ok(instr2("JP Z,a16", JP, Flag(FlagId::Z), Byte, 12)),
             0xCB => try_from_cb(opcodes.1),
-            0xCC => Ok(instr2("CALL Z,a16", CALL, Flag(FlagId::Z), Byte, 16)),
+            0xCC => Ok(instr2("CALL Z,a16", CALL, Flag(FlagId::Z), Byte, 12)),
             0xCD => Ok(instr1("CALL a16", CALL, Byte, 24)),
-            0xCE => Ok(instr1("ADC A,a8", ADC, Byte, 8)),
+            0xCE => Ok(instr1("ADC A,d8", ADC, Byte, 8)),
             0xCF => Ok(instr1("RST 08H", RST, Value(0x08), 16)),
 
             0xD0 => Ok(instr1("RET NC", RET, Flag(FlagId::NC), 8)),
             0xD1 => Ok(instr1("POP DE", POP, Register16(Register16Id::DE), 12)),
             0xD2 => Ok(instr2("JP NC,a16", JP, Flag(FlagId::NC), Byte, 12)),
             // 0xD3 not used
-            0xD4 => Ok(instr2("CALL NC,a16", CALL, Flag(FlagId::NC), Byte, 16)),
+            0xD4 => Ok(instr2("CALL NC,a16", CALL, Flag(FlagId::NC), Byte, 12)),
             0xD5 => Ok(instr1("PUSH DE", PUSH, Register16(Register16Id::DE), 16)),
             0xD6 => Ok(instr1("SUB d8", SUB, Byte, 8)),
             0xD7 => Ok(instr1("RST 10H", RST, Value(0x10), 16)),
@@ -372,7 +488,7 @@ impl TryFrom<(u8, u8)> for Instruction {
             0xD9 => Ok(instr0("RETI", RETI, 16)),
             0xDA => Ok(instr2("JP C,a16", JP, Flag(FlagId::C), Byte, 12)),
             // 0xDB not used
-            0xDC => Ok(instr2("CALL C,a16", CALL, Flag(FlagId::C), Byte, 16)),
+            0xDC => Ok(instr2("CALL C,a16", CALL, Flag(FlagId::C), Byte, 12)),
             // 0xDD not used
             0xDE => Ok(instr1("SBC d8", SBC, Byte, 8)),
             0xDF => Ok(instr1("RST 18H", RST, Value(0x18), 16)),
@@ -400,7 +516,7 @@ impl TryFrom<(u8, u8)> for Instruction {
             0xF2 => Ok(instr2("LD A,(C)", LD, Register(RegisterId::A), IoPort(RegisterId::C), 8)),
             0xF3 => Ok(instr0("DI", DI, 4)),
             // 0xF4 not used
-            0xF5 => Ok(instr1("PUSH HL", PUSH, Register16(Register16Id::AF), 16)),
+            0xF5 => Ok(instr1("PUSH AF", PUSH, Register16(Register16Id::AF), 16)),
             0xF6 => Ok(instr1("OR d8", OR, Byte, 8)),
             0xF7 => Ok(instr1("RST 30H", RST, Value(0x30), 16)),
 
@@ -428,14 +544,14 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x05 => Ok(instr1("RLC L", RLC, Register(RegisterId::L), 8)),
         0x06 => Ok(instr1("RLC (HL)", RLC, IndirectAddress(HL), 16)),
         0x07 => Ok(instr1("RLC A", RLC, Register(RegisterId::A), 8)),
-        0x08 => Ok(instr1("RLC B", RRC, Register(RegisterId::B), 8)),
-        0x09 => Ok(instr1("RLC C", RRC, Register(RegisterId::C), 8)),
-        0x0A => Ok(instr1("RLC D", RRC, Register(RegisterId::D), 8)),
-        0x0B => Ok(instr1("RLC E", RRC, Register(RegisterId::E), 8)),
-        0x0C => Ok(instr1("RLC H", RRC, Register(RegisterId::H), 8)),
-        0x0D => Ok(instr1("RLC L", RRC, Register(RegisterId::L), 8)),
-        0x0E => Ok(instr1("RLC (HL)", RRC, IndirectAddress(HL), 16)),
-        0x0F => Ok(instr1("RLC A", RRC, Register(RegisterId::A), 8)),
+        0x08 => Ok(instr1("RRC B", RRC, Register(RegisterId::B), 8)),
+        0x09 => Ok(instr1("RRC C", RRC, Register(RegisterId::C), 8)),
+        0x0A => Ok(instr1("RRC D", RRC, Register(RegisterId::D), 8)),
+        0x0B => Ok(instr1("RRC E", RRC, Register(RegisterId::E), 8)),
+        0x0C => Ok(instr1("RRC H", RRC, Register(RegisterId::H), 8)),
+        0x0D => Ok(instr1("RRC L", RRC, Register(RegisterId::L), 8)),
+        0x0E => Ok(instr1("RRC (HL)", RRC, IndirectAddress(HL), 16)),
+        0x0F => Ok(instr1("RRC A", RRC, Register(RegisterId::A), 8)),
 
         0x10 => Ok(instr1("RL B", RL, Register(RegisterId::B), 8)),
         0x11 => Ok(instr1("RL C", RL, Register(RegisterId::C), 8)),
@@ -494,7 +610,7 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x43 => Ok(instr2("BIT 0,E", BIT, Value(0), Register(RegisterId::E), 8)),
         0x44 => Ok(instr2("BIT 0,H", BIT, Value(0), Register(RegisterId::H), 8)),
         0x45 => Ok(instr2("BIT 0,L", BIT, Value(0), Register(RegisterId::L), 8)),
-        0x46 => Ok(instr2("BIT 0,(HL)", BIT, Value(0), IndirectAddress(HL), 16)),
+        0x46 => Ok(instr2("BIT 0,(HL)", BIT, Value(0), IndirectAddress(HL), 12)),
         0x47 => Ok(instr2("BIT 0,A", BIT, Value(0), Register(RegisterId::A), 8)),
         0x48 => Ok(instr2("BIT 1,B", BIT, Value(1), Register(RegisterId::B), 8)),
         0x49 => Ok(instr2("BIT 1,C", BIT, Value(1), Register(RegisterId::C), 8)),
@@ -502,7 +618,7 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x4B => Ok(instr2("BIT 1,E", BIT, Value(1), Register(RegisterId::E), 8)),
         0x4C => Ok(instr2("BIT 1,H", BIT, Value(1), Register(RegisterId::H), 8)),
         0x4D => Ok(instr2("BIT 1,L", BIT, Value(1), Register(RegisterId::L), 8)),
-        0x4E => Ok(instr2("BIT 1,(HL)", BIT, Value(1), IndirectAddress(HL), 16)),
+        0x4E => Ok(instr2("BIT 1,(HL)", BIT, Value(1), IndirectAddress(HL), 12)),
         0x4F => Ok(instr2("BIT 1,A", BIT, Value(1), Register(RegisterId::A), 8)),
 
         0x50 => Ok(instr2("BIT 2,B", BIT, Value(2), Register(RegisterId::B), 8)),
@@ -511,7 +627,7 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x53 => Ok(instr2("BIT 2,E", BIT, Value(2), Register(RegisterId::E), 8)),
         0x54 => Ok(instr2("BIT 2,H", BIT, Value(2), Register(RegisterId::H), 8)),
         0x55 => Ok(instr2("BIT 2,L", BIT, Value(2), Register(RegisterId::L), 8)),
-        0x56 => Ok(instr2("BIT 2,(HL)", BIT, Value(2), IndirectAddress(HL), 16)),
+        0x56 => Ok(instr2("BIT 2,(HL)", BIT, Value(2), IndirectAddress(HL), 12)),
         0x57 => Ok(instr2("BIT 2,A", BIT, Value(2), Register(RegisterId::A), 8)),
         0x58 => Ok(instr2("BIT 3,B", BIT, Value(3), Register(RegisterId::B), 8)),
         0x59 => Ok(instr2("BIT 3,C", BIT, Value(3), Register(RegisterId::C), 8)),
@@ -519,7 +635,7 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x5B => Ok(instr2("BIT 3,E", BIT, Value(3), Register(RegisterId::E), 8)),
         0x5C => Ok(instr2("BIT 3,H", BIT, Value(3), Register(RegisterId::H), 8)),
         0x5D => Ok(instr2("BIT 3,L", BIT, Value(3), Register(RegisterId::L), 8)),
-        0x5E => Ok(instr2("BIT 3,(HL)", BIT, Value(3), IndirectAddress(HL), 16)),
+        0x5E => Ok(instr2("BIT 3,(HL)", BIT, Value(3), IndirectAddress(HL), 12)),
         0x5F => Ok(instr2("BIT 3,A", BIT, Value(3), Register(RegisterId::A), 8)),
 
         0x60 => Ok(instr2("BIT 4,B", BIT, Value(4), Register(RegisterId::B), 8)),
@@ -528,15 +644,15 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x63 => Ok(instr2("BIT 4,E", BIT, Value(4), Register(RegisterId::E), 8)),
         0x64 => Ok(instr2("BIT 4,H", BIT, Value(4), Register(RegisterId::H), 8)),
         0x65 => Ok(instr2("BIT 4,L", BIT, Value(4), Register(RegisterId::L), 8)),
-        0x66 => Ok(instr2("BIT 4,(HL)", BIT, Value(4), IndirectAddress(HL), 16)),
-        0x67 => Ok(instr2("BIT A", BIT, Value(4), Register(RegisterId::A), 8)),
-        0x68 => Ok(instr2("BIT B", BIT, Value(5), Register(RegisterId::B), 8)),
+        0x66 => Ok(instr2("BIT 4,(HL)", BIT, Value(4), IndirectAddress(HL), 12)),
+        0x67 => Ok(instr2("BIT 4,A", BIT, Value(4), Register(RegisterId::A), 8)),
+        0x68 => Ok(instr2("BIT 5,B", BIT, Value(5), Register(RegisterId::B), 8)),
         0x69 => Ok(instr2("BIT 5,C", BIT, Value(5), Register(RegisterId::C), 8)),
         0x6A => Ok(instr2("BIT 5,D", BIT, Value(5), Register(RegisterId::D), 8)),
         0x6B => Ok(instr2("BIT 5,E", BIT, Value(5), Register(RegisterId::E), 8)),
         0x6C => Ok(instr2("BIT 5,H", BIT, Value(5), Register(RegisterId::H), 8)),
         0x6D => Ok(instr2("BIT 5,L", BIT, Value(5), Register(RegisterId::L), 8)),
-        0x6E => Ok(instr2("BIT 5,(HL)", BIT, Value(5), IndirectAddress(HL), 16)),
+        0x6E => Ok(instr2("BIT 5,(HL)", BIT, Value(5), IndirectAddress(HL), 12)),
         0x6F => Ok(instr2("BIT 5,A", BIT, Value(5), Register(RegisterId::A), 8)),
 
         0x70 => Ok(instr2("BIT 6,B", BIT, Value(6), Register(RegisterId::B), 8)),
@@ -545,7 +661,7 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x73 => Ok(instr2("BIT 6,E", BIT, Value(6), Register(RegisterId::E), 8)),
         0x74 => Ok(instr2("BIT 6,H", BIT, Value(6), Register(RegisterId::H), 8)),
         0x75 => Ok(instr2("BIT 6,L", BIT, Value(6), Register(RegisterId::L), 8)),
-        0x76 => Ok(instr2("BIT 6,(HL)", BIT, Value(6), IndirectAddress(HL), 16)),
+        0x76 => Ok(instr2("BIT 6,(HL)", BIT, Value(6), IndirectAddress(HL), 12)),
         0x77 => Ok(instr2("BIT 6,A", BIT, Value(6), Register(RegisterId::A), 8)),
         0x78 => Ok(instr2("BIT 7,B", BIT, Value(7), Register(RegisterId::B), 8)),
         0x79 => Ok(instr2("BIT 7,C", BIT, Value(7), Register(RegisterId::C), 8)),
@@ -553,7 +669,7 @@ fn try_from_cb(opcode: u8) -> Result<Instruction, ()> {
         0x7B => Ok(instr2("BIT 7,E", BIT, Value(7), Register(RegisterId::E), 8)),
         0x7C => Ok(instr2("BIT 7,H", BIT, Value(7), Register(RegisterId::H), 8)),
         0x7D => Ok(instr2("BIT 7,L", BIT, Value(7), Register(RegisterId::L), 8)),
-        0x7E => Ok(instr2("BIT 7,(HL)", BIT, Value(7), IndirectAddress(HL), 16)),
+        0x7E => Ok(instr2("BIT 7,(HL)", BIT, Value(7), IndirectAddress(HL), 12)),
         0x7F => Ok(instr2("BIT 7,A", BIT, Value(7), Register(RegisterId::A), 8)),
 
         0x80 => Ok(instr2("RES 0,B", RES, Value(0), Register(RegisterId::B), 8)),
@@ -0,0 +1,143 @@
+//! Screenshot-based regression testing.
+//!
+//! Every ROM of a directory is run for a fixed number of frames, or until it stops, and the
+//! rendered screen is compared with a golden image named after the ROM. Goldens are PNG
+//! images written by `screenshot::encode_png` with the grayscale palette, and are compared
+//! pixel by pixel with `screenshot::decode_png`.
+
+use std::any::Any;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use crate::cartridge::Cartridge;
+use crate::cpu::{self, IllegalOpcodePolicy, StepResult, CYCLES_PER_FRAME};
+use crate::lcd::{self, Frame};
+use crate::memory::Mmu;
+use crate::screenshot::{decode_png, encode_png, Palette};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The golden image was written from the rendered screen
+    Updated,
+    /// The rendered screen differs from the golden image, saved next to it as `.actual.png`
+    Mismatch,
+    MissingGolden,
+    /// The emulator failed while running the ROM
+    Error(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RomResult {
+    pub rom: PathBuf,
+    pub outcome: Outcome,
+}
+
+impl RomResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.outcome, Outcome::Passed | Outcome::Updated)
+    }
+}
+
+impl fmt::Display for RomResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.rom.file_name().unwrap_or_default().to_string_lossy();
+
+        match &self.outcome {
+            Outcome::Passed => write!(f, "PASS     {}", name),
+            Outcome::Updated => write!(f, "UPDATED  {}", name),
+            Outcome::Mismatch => write!(f, "FAIL     {} (screen differs from golden)", name),
+            Outcome::MissingGolden => write!(f, "FAIL     {} (no golden image)", name),
+            Outcome::Error(message) => write!(f, "ERROR    {} ({})", name, message),
+        }
+    }
+}
+
+/// Runs a ROM for at most `frames` frames and renders the screen.
+pub fn run_rom(path: &Path, frames: u64) -> Result<Frame, String> {
+    let cartridge = Cartridge::load(&path.to_string_lossy()).map_err(|e| e.to_string())?;
+
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        while cpu.cycles() < frames * CYCLES_PER_FRAME {
+            match cpu.step(&mut mmu) {
+                StepResult::Stopped => break,
+                StepResult::IllegalOpcode { pc, opcode } => {
+                    return Err(format!("Illegal opcode {:#04x} at {:#06x}", opcode, pc));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    })).map_err(panic_message)??;
+
+    Ok(lcd::render(&mmu))
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or("panic".to_owned(), |s| s.to_string()),
+    }
+}
+
+/// Checks every `.gb` ROM of `rom_dir` against `<golden_dir>/<rom name>.png`. With `update`,
+/// goldens are written instead of compared.
+pub fn check_dir(rom_dir: &Path, golden_dir: &Path, frames: u64, update: bool) -> io::Result<Vec<RomResult>> {
+    let mut roms: Vec<PathBuf> = fs::read_dir(rom_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    roms.retain(|path| path.extension().is_some_and(|ext| ext == "gb"));
+    roms.sort();
+
+    if update {
+        fs::create_dir_all(golden_dir)?;
+    }
+
+    roms.into_iter()
+        .map(|rom| {
+            let outcome = check_rom(&rom, golden_dir, frames, update)?;
+            Ok(RomResult { rom, outcome })
+        })
+        .collect()
+}
+
+fn check_rom(rom: &Path, golden_dir: &Path, frames: u64, update: bool) -> io::Result<Outcome> {
+    let frame = match run_rom(rom, frames) {
+        Ok(frame) => frame,
+        Err(message) => return Ok(Outcome::Error(message)),
+    };
+
+    let png = encode_png(&frame, Palette::Grayscale);
+    let stem = rom.file_stem().unwrap_or_default().to_string_lossy();
+    let golden = golden_dir.join(format!("{}.png", stem));
+    let actual = golden_dir.join(format!("{}.actual.png", stem));
+
+    if update {
+        fs::write(&golden, &png)?;
+        if actual.exists() {
+            fs::remove_file(&actual)?;
+        }
+        return Ok(Outcome::Updated);
+    }
+
+    match fs::read(&golden) {
+        // Pixels are compared rather than the files, which don't have to be written by the
+        // current version of the encoder
+        Ok(expected) if decode_png(&expected).is_some_and(|golden| golden.pixels() == frame.pixels()) => {
+            Ok(Outcome::Passed)
+        }
+        Ok(_) => {
+            fs::write(&actual, &png)?;
+            Ok(Outcome::Mismatch)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Outcome::MissingGolden),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,203 @@
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+use crate::lcd::{Frame, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Colors used to display the four DMG shades, from lightest to darkest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Palette {
+    Grayscale,
+    /// The green tint of the original DMG screen
+    ClassicGreen,
+    Custom([[u8; 3]; 4]),
+}
+
+impl Palette {
+    pub fn colors(&self) -> [[u8; 3]; 4] {
+        match self {
+            Palette::Grayscale => [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0x00, 0x00, 0x00]],
+            Palette::ClassicGreen => [[0x9B, 0xBC, 0x0F], [0x8B, 0xAC, 0x0F], [0x30, 0x62, 0x30], [0x0F, 0x38, 0x0F]],
+            Palette::Custom(colors) => *colors,
+        }
+    }
+}
+
+/// Parses `grayscale`, `green`, or four comma-separated `RRGGBB` colors.
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grayscale" | "gray" => return Ok(Palette::Grayscale),
+            "green" | "classic" => return Ok(Palette::ClassicGreen),
+            _ => {}
+        }
+
+        let colors: Vec<[u8; 3]> = s.split(',')
+            .map(|color| {
+                let rgb = u32::from_str_radix(color.trim_start_matches('#'), 16)
+                    .ok()
+                    .filter(|_| color.trim_start_matches('#').len() == 6)
+                    .ok_or(format!("Invalid color '{}'", color))?;
+                Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+            })
+            .collect::<Result<_, String>>()?;
+
+        colors.try_into()
+            .map(Palette::Custom)
+            .map_err(|_| format!("Expected 4 colors in palette '{}'", s))
+    }
+}
+
+/// Converts a frame to RGB pixels, row by row.
+pub fn to_rgb(frame: &Frame, palette: Palette) -> Vec<u8> {
+    let colors = palette.colors();
+    frame.pixels().iter().flat_map(|&shade| colors[shade as usize]).collect()
+}
+
+pub fn save_png(path: &str, frame: &Frame, palette: Palette) -> io::Result<()> {
+    fs::write(path, encode_png(frame, palette))
+}
+
+/// Encodes a frame as a PNG image with a 4-color palette. The image data is stored
+/// uncompressed, which keeps the output identical for identical frames.
+pub fn encode_png(frame: &Frame, palette: Palette) -> Vec<u8> {
+    // Each scanline starts with its filter type, 0 (none), followed by 4 pixels per byte
+    let mut raw = Vec::with_capacity(SCREEN_HEIGHT * (SCREEN_WIDTH / 4 + 1));
+    for line in frame.pixels().chunks(SCREEN_WIDTH) {
+        raw.push(0);
+        raw.extend(line.chunks(4).map(|p| p[0] << 6 | p[1] << 4 | p[2] << 2 | p[3]));
+    }
+
+    let mut header = Vec::new();
+    header.extend((SCREEN_WIDTH as u32).to_be_bytes());
+    header.extend((SCREEN_HEIGHT as u32).to_be_bytes());
+    // Bit depth 2, color type 3 (indexed), default compression, filtering and no interlacing
+    header.extend([2, 3, 0, 0, 0]);
+
+    let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"PLTE", palette.colors().as_flattened());
+    write_chunk(&mut png, b"IDAT", &zlib_store(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Decodes the shades of a PNG image written by `encode_png`, whatever its palette. Only
+/// uncompressed, unfiltered 2-bit images of the screen size are supported.
+pub fn decode_png(png: &[u8]) -> Option<Frame> {
+    let mut rest = png.strip_prefix(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A])?;
+    let mut header = None;
+    let mut zlib = Vec::new();
+
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let kind = &rest[4..8];
+        let data = rest.get(8..8 + len)?;
+        rest = rest.get(12 + len..)?;
+
+        match kind {
+            b"IHDR" => header = Some(data.to_vec()),
+            b"IDAT" => zlib.extend(data),
+            _ => {}
+        }
+    }
+
+    let mut expected_header = Vec::new();
+    expected_header.extend((SCREEN_WIDTH as u32).to_be_bytes());
+    expected_header.extend((SCREEN_HEIGHT as u32).to_be_bytes());
+    expected_header.extend([2, 3, 0, 0, 0]);
+    if header? != expected_header {
+        return None;
+    }
+
+    let raw = zlib_unstore(&zlib)?;
+    let mut pixels = Vec::with_capacity(SCREEN_WIDTH * SCREEN_HEIGHT);
+    for line in raw.chunks(SCREEN_WIDTH / 4 + 1) {
+        let (&filter, bytes) = line.split_first()?;
+        if filter != 0 {
+            return None;
+        }
+        pixels.extend(bytes.iter().flat_map(|&b| [b >> 6, b >> 4 & 3, b >> 2 & 3, b & 3]));
+    }
+
+    if pixels.len() != SCREEN_WIDTH * SCREEN_HEIGHT {
+        return None;
+    }
+    Some(Frame::from_pixels(pixels))
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+
+    let crc = crc32(kind.iter().chain(data.iter()));
+    png.extend(crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream made of uncompressed deflate blocks.
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+
+    let blocks: Vec<&[u8]> = data.chunks(0xFFFF).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i == blocks.len() - 1;
+        let len = block.len() as u16;
+
+        stream.push(last as u8);
+        stream.extend(len.to_le_bytes());
+        stream.extend((!len).to_le_bytes());
+        stream.extend(*block);
+    }
+
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+/// Reads a zlib stream made of uncompressed deflate blocks.
+fn zlib_unstore(stream: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = stream.get(2..)?;
+    let mut data = Vec::new();
+
+    loop {
+        let (&block_header, block) = rest.split_first()?;
+        // Only stored blocks (BTYPE 0)
+        if block_header & !1 != 0 {
+            return None;
+        }
+
+        let len = u16::from_le_bytes(block.get(..2)?.try_into().unwrap()) as usize;
+        data.extend(block.get(4..4 + len)?);
+        rest = &block[4 + len..];
+
+        if block_header & 1 == 1 {
+            return Some(data);
+        }
+    }
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    b << 16 | a
+}
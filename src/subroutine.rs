@@ -0,0 +1,124 @@
+//! Calling a single routine of a ROM from Rust, to unit-test assembly code.
+//!
+//! ```no_run
+//! use ruboy::cartridge::Cartridge;
+//! use ruboy::cpu;
+//! use ruboy::memory::Mmu;
+//! use ruboy::subroutine;
+//!
+//! let mut cpu = cpu::init_cpu();
+//! let mut mmu = Mmu::new(Cartridge::new("game.gb"));
+//!
+//! let mut state = cpu.state();
+//! state.set_hl(0xC000);
+//! cpu.set_state(state);
+//! mmu[0xC000] = 0x42;
+//!
+//! let call = subroutine::call(&mut cpu, &mut mmu, 0x0150, 10_000).unwrap();
+//! println!("A={:02X} after {} cycles", call.state.a, call.cycles);
+//! ```
+
+use std::fmt;
+
+use crate::bus::Bus;
+use crate::cpu::{Cpu, CpuState, StepResult};
+
+/// Return address pushed before jumping to the routine. 0xFFFF is the IE register, which
+/// routines don't normally execute, but the sentinel only works as long as the routine
+/// doesn't jump there itself.
+pub const RETURN_ADDRESS: u16 = 0xFFFF;
+
+/// Outcome of a routine which returned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    /// Registers after the matching RET
+    pub state: CpuState,
+    /// Clock cycles spent in the routine, including the RET
+    pub cycles: u64,
+    /// Bytes that differ after the call, in address order. The pushed return address is
+    /// below the stack pointer, and registers like DIV change on their own.
+    pub memory: Vec<MemoryChange>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryChange {
+    pub address: u16,
+    pub before: u8,
+    pub after: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    /// `max_cycles` elapsed before the routine returned
+    CycleLimit(CpuState),
+    /// A step reported something else than `StepResult::Continue` before the routine returned
+    Interrupted(StepResult, CpuState),
+    /// The CPU locked up on an illegal opcode, with `IllegalOpcodePolicy::Lock`
+    Locked { pc: u16, opcode: u8 },
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::CycleLimit(state) => {
+                write!(f, "Routine didn't return in time, PC={:04X}", state.pc)
+            }
+            CallError::Interrupted(StepResult::IllegalOpcode { pc, opcode }, _) => {
+                write!(f, "Illegal opcode {:02X} at PC={:04X}", opcode, pc)
+            }
+            CallError::Interrupted(result, state) => {
+                write!(f, "Routine ended with {:?} at PC={:04X}", result, state.pc)
+            }
+            CallError::Locked { pc, opcode } => {
+                write!(f, "CPU locked up on illegal opcode {:02X} at PC={:04X}", opcode, pc)
+            }
+        }
+    }
+}
+
+/// Calls the routine at `address` with the current CPU state and memory, as a CALL would,
+/// and runs it until the matching RET, or for at most `max_cycles`.
+pub fn call(cpu: &mut Cpu, bus: &mut impl Bus, address: u16, max_cycles: u64) -> Result<Call, CallError> {
+    let before = snapshot(bus);
+    let start = cpu.cycles();
+    let sp = cpu.regs.sp;
+
+    let [low, high] = RETURN_ADDRESS.to_le_bytes();
+    bus.poke(sp.wrapping_sub(1), high);
+    bus.poke(sp.wrapping_sub(2), low);
+    cpu.regs.sp = sp.wrapping_sub(2);
+    cpu.regs.pc = address;
+
+    // Nested calls return to other addresses, and jumps to RETURN_ADDRESS leave SP elsewhere
+    while cpu.regs.pc != RETURN_ADDRESS || cpu.regs.sp != sp {
+        if cpu.cycles() - start >= max_cycles {
+            return Err(CallError::CycleLimit(cpu.state()));
+        }
+
+        let result = cpu.step(bus);
+        if result != StepResult::Continue {
+            return Err(CallError::Interrupted(result, cpu.state()));
+        }
+        // PC stays on the opcode the CPU locked up on
+        if cpu.is_locked() {
+            return Err(CallError::Locked { pc: cpu.regs.pc, opcode: bus.peek(cpu.regs.pc) });
+        }
+    }
+
+    let memory = snapshot(bus).into_iter()
+        .zip(before)
+        .enumerate()
+        .filter(|(_, (after, before))| after != before)
+        .map(|(address, (after, before))| MemoryChange { address: address as u16, before, after })
+        .collect();
+
+    Ok(Call {
+        state: cpu.state(),
+        cycles: cpu.cycles() - start,
+        memory,
+    })
+}
+
+fn snapshot(bus: &impl Bus) -> Vec<u8> {
+    (0..=0xFFFF).map(|address| bus.peek(address)).collect()
+}
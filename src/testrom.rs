@@ -0,0 +1,98 @@
+//! Result protocols of test ROM suites.
+
+use crate::cpu::{Cpu, Registers, StepResult};
+use crate::memory::Mmu;
+use crate::opcodes::RegisterId::{B, C, D, E, H, L};
+
+/// `LD B,B`, executed by Mooneye test ROMs once the result is in the registers
+pub const MOONEYE_BREAKPOINT: u8 = 0x40;
+
+const MOONEYE_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+
+/// Status byte of the blargg result area, followed by the signature and the result text
+const BLARGG_STATUS: u16 = 0xA000;
+const BLARGG_SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const BLARGG_TEXT: u16 = 0xA004;
+/// Status written while the test is still running
+const BLARGG_RUNNING: u8 = 0x80;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestResult {
+    Passed,
+    /// The ROM reported a failure, with its explanation if it gave one
+    Failed(String),
+    /// The ROM stopped or ran out of cycles without reporting a result
+    Unfinished,
+}
+
+/// Reads the result of a Mooneye test ROM from the registers, once it hit the breakpoint.
+pub fn mooneye_result(regs: &Registers) -> TestResult {
+    let values = [regs[B], regs[C], regs[D], regs[E], regs[H], regs[L]];
+
+    if values == MOONEYE_PASS {
+        TestResult::Passed
+    } else {
+        TestResult::Failed(format!("B={:02X} C={:02X} D={:02X} E={:02X} H={:02X} L={:02X}",
+                                   values[0], values[1], values[2], values[3], values[4], values[5]))
+    }
+}
+
+/// Runs a Mooneye test ROM until it hits the `LD B,B` breakpoint or `max_cycles` elapse.
+pub fn run_mooneye(cpu: &mut Cpu, mmu: &mut Mmu, max_cycles: u64) -> TestResult {
+    cpu.set_breakpoint_opcode(Some(MOONEYE_BREAKPOINT));
+
+    while cpu.cycles() < max_cycles {
+        match cpu.step(mmu) {
+            StepResult::Continue => {}
+            StepResult::Breakpoint => return mooneye_result(&cpu.regs),
+            StepResult::Stopped | StepResult::IllegalOpcode { .. } => return TestResult::Unfinished,
+        }
+    }
+
+    TestResult::Unfinished
+}
+
+/// Reads the result of a blargg test ROM. ROMs writing the signature at 0xA001 report their
+/// status at 0xA000 and their output from 0xA004, the others only print to the serial port.
+/// Either way, the output has to contain "Passed" for the test to pass.
+pub fn blargg_result(mmu: &Mmu) -> TestResult {
+    let signature = [mmu[BLARGG_STATUS + 1], mmu[BLARGG_STATUS + 2], mmu[BLARGG_STATUS + 3]];
+
+    let (status, text) = if signature == BLARGG_SIGNATURE {
+        (Some(mmu[BLARGG_STATUS]), blargg_text(mmu))
+    } else {
+        (None, String::from_utf8_lossy(mmu.serial_output()).into_owned())
+    };
+
+    match status {
+        Some(BLARGG_RUNNING) => TestResult::Unfinished,
+        Some(0) if text.contains("Passed") => TestResult::Passed,
+        None if text.contains("Failed") => TestResult::Failed(text.trim().to_owned()),
+        None if text.contains("Passed") => TestResult::Passed,
+        None => TestResult::Unfinished,
+        _ => TestResult::Failed(text.trim().to_owned()),
+    }
+}
+
+/// Zero-terminated text of the result area.
+fn blargg_text(mmu: &Mmu) -> String {
+    let bytes: Vec<u8> = (BLARGG_TEXT..BLARGG_STATUS + 0x2000)
+        .map(|address| mmu[address])
+        .take_while(|&b| b != 0)
+        .collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Runs a blargg test ROM until it stops, hits a breakpoint or an illegal opcode, or
+/// `max_cycles` elapse, and reads its result.
+pub fn run_blargg(cpu: &mut Cpu, mmu: &mut Mmu, max_cycles: u64) -> TestResult {
+    // A locked CPU would only burn the remaining cycles
+    while cpu.cycles() < max_cycles && !cpu.is_locked() {
+        if cpu.step(mmu) != StepResult::Continue {
+            break;
+        }
+    }
+
+    blargg_result(mmu)
+}
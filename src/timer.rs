@@ -0,0 +1,72 @@
+//! DIV and TIMA timers.
+//!
+//! DIV is the upper byte of a 16-bit counter incremented on every clock cycle. TIMA is
+//! incremented whenever the counter bit selected by TAC goes from 1 to 0, and is reloaded
+//! from TMA with a timer interrupt request when it overflows.
+
+/// Offsets of the timer registers in the I/O ports
+const DIV: usize = 0x04;
+const TIMA: usize = 0x05;
+const TMA: usize = 0x06;
+const TAC: usize = 0x07;
+const IF: usize = 0x0F;
+
+const TIMER_INTERRUPT: u8 = 0x04;
+
+pub struct Timer {
+    counter: u16,
+}
+
+impl Timer {
+    pub fn new(div: u8) -> Timer {
+        Timer {
+            counter: (div as u16) << 8,
+        }
+    }
+
+    /// Advances the timer, updating the registers stored in `io_ports`.
+    pub fn tick(&mut self, io_ports: &mut [u8], cycles: u8) {
+        for _ in 0..cycles {
+            let before = self.timer_bit(io_ports[TAC]);
+            self.counter = self.counter.wrapping_add(1);
+
+            if before && !self.timer_bit(io_ports[TAC]) {
+                increment_tima(io_ports);
+            }
+        }
+
+        io_ports[DIV] = (self.counter >> 8) as u8;
+    }
+
+    /// Writing to DIV resets the counter, which increments TIMA if the selected bit was set.
+    pub fn reset(&mut self, io_ports: &mut [u8]) {
+        if self.timer_bit(io_ports[TAC]) {
+            increment_tima(io_ports);
+        }
+
+        self.counter = 0;
+        io_ports[DIV] = 0;
+    }
+
+    fn timer_bit(&self, tac: u8) -> bool {
+        let bit = match tac & 0x03 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            _ => 7,
+        };
+
+        tac & 0x04 != 0 && self.counter & (1 << bit) != 0
+    }
+}
+
+fn increment_tima(io_ports: &mut [u8]) {
+    let (value, overflow) = io_ports[TIMA].overflowing_add(1);
+
+    if overflow {
+        io_ports[TIMA] = io_ports[TMA];
+        io_ports[IF] |= TIMER_INTERRUPT;
+    } else {
+        io_ports[TIMA] = value;
+    }
+}
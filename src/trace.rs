@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+
+use crate::cpu::Registers;
+use crate::bus::Bus;
+use crate::opcodes::RegisterId::{A, B, C, D, E, H, L};
+
+/// Logs the CPU state before each instruction, one line per instruction, using the
+/// Gameboy Doctor format:
+///
+/// `A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02`
+pub struct Tracer {
+    sink: Sink,
+    enabled: bool,
+    /// First write error, which disabled the tracer
+    error: Option<io::Error>,
+}
+
+enum Sink {
+    Writer(Box<dyn Write>),
+    Callback(Box<dyn FnMut(&str)>),
+}
+
+impl Tracer {
+    /// Creates a tracer writing to the file at `path`, truncating it if it exists.
+    pub fn to_file(path: &str) -> io::Result<Tracer> {
+        Ok(Self::to_writer(BufWriter::new(File::create(path)?)))
+    }
+
+    pub fn to_writer<W: Write + 'static>(writer: W) -> Tracer {
+        Tracer {
+            sink: Sink::Writer(Box::new(writer)),
+            enabled: true,
+            error: None,
+        }
+    }
+
+    /// Creates a tracer calling `callback` with each line (without the trailing newline).
+    pub fn with_callback<F: FnMut(&str) + 'static>(callback: F) -> Tracer {
+        Tracer {
+            sink: Sink::Callback(Box::new(callback)),
+            enabled: true,
+            error: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pauses or resumes tracing without closing the underlying sink.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Error which stopped tracing, if writing a line failed.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Logs a line. Tracing stops at the first write error, which is kept for `error`, so
+    /// that a full disk or a closed pipe doesn't stop the emulation.
+    pub fn trace(&mut self, regs: &Registers, bus: &impl Bus) {
+        if !self.enabled || self.error.is_some() {
+            return;
+        }
+
+        let line = format_state(regs, bus);
+
+        match &mut self.sink {
+            Sink::Writer(writer) => {
+                if let Err(e) = writeln!(writer, "{}", line) {
+                    self.error = Some(e);
+                }
+            }
+            Sink::Callback(callback) => callback(&line),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Writer(writer) => writer.flush(),
+            Sink::Callback(_) => Ok(()),
+        }
+    }
+}
+
+/// Formats the registers and the 4 bytes at PC as a Gameboy Doctor log line.
+pub fn format_state(regs: &Registers, bus: &impl Bus) -> String {
+    let pc = regs.pc;
+
+    format!(
+        "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+        regs[A], regs.flags.get_f(), regs[B], regs[C], regs[D], regs[E], regs[H], regs[L],
+        regs.sp, pc,
+        bus.peek(pc), bus.peek(pc.wrapping_add(1)), bus.peek(pc.wrapping_add(2)), bus.peek(pc.wrapping_add(3))
+    )
+}
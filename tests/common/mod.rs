@@ -1,6 +1,19 @@
+#![allow(dead_code)]
+
 use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
 use ruboy::cpu::{Cpu, Flag};
 
+/// Ends test programs. It's an illegal opcode, so it can't be mistaken for an instruction.
+pub const EXIT: u8 = 0xFD;
+
+/// Creates a CPU stopping when it reaches `EXIT`.
+pub fn init_cpu() -> Cpu {
+    let mut cpu = cpu::init_cpu();
+    cpu.set_exit_opcode(Some(EXIT));
+    cpu
+}
+
 pub fn build_cartridge(program: Vec<u8>) -> Cartridge {
     let mut content = vec![0; 0x8000];
 
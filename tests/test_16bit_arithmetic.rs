@@ -1,11 +1,9 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::Register16Id::{BC, DE, HL, SP};
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -21,10 +19,10 @@ macro_rules! arithmetic_tests {
                 0x11, 0x44, 0x33, // LD DE, $3344
                 0x21, 0x66, 0x55, // LD HL, $5566
                 opcode, // ADD x, y
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.run(&mut mmu);
@@ -46,10 +44,10 @@ arithmetic_tests! {
 fn test_ADD_SPn() {
     let cartridge = build_cartridge(vec![
         0xE8, 0xAA, // ADD SP, $AA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
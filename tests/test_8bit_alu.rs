@@ -1,12 +1,10 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::cpu::Flag;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::{A, B, C, D, E, H, L};
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -23,11 +21,10 @@ macro_rules! add_tests {
                 0x11, 0x44, 0x33, // LD DE, $3344
                 0x21, 0x66, 0x55, // LD HL, $5566
                 opcode, 0x69, // ADD x, y
-                0x10, 0x00, // STOP
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.regs.flags.c = true;
@@ -57,10 +54,10 @@ fn test_ADD_AHL() {
         0x26, 0xC0, // LD H, $C0
         0x2E, 0x00, // LD L, $00
         0x86, // ADD A, (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     mmu[0xC000] = 8;
 
@@ -74,17 +71,17 @@ fn test_ADD_An() {
     let cartridge = build_cartridge(vec![
         0x3E, 0x12, // LD A, $12
         0xC6, 0x09, // ADD A, $09
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
 
     assert_eq!(cpu.regs[A], 0x1B);
-    assert_eq!(false, cpu.regs[Flag::Z]);
-    assert_eq!(false, cpu.regs[Flag::N]);
+    assert!(!cpu.regs[Flag::Z]);
+    assert!(!cpu.regs[Flag::N]);
 }
 
 #[test]
@@ -92,16 +89,16 @@ fn test_ADD_flags() {
     let cartridge = build_cartridge(vec![
         0x3E, 0xFF, // LD A, $12
         0xC6, 0x01, // ADD A, $01
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
 
-    assert_eq!(true, cpu.regs[Flag::Z]);
-    assert_eq!(false, cpu.regs[Flag::N]);
+    assert!(cpu.regs[Flag::Z]);
+    assert!(!cpu.regs[Flag::N]);
     // TODO test flags H and C
 }
 
@@ -122,10 +119,10 @@ fn test_ADC_AHL() {
         0x26, 0xC0, // LD H, $C0
         0x2E, 0x00, // LD L, $00
         0x8E, // ADC A, (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     mmu[0xC000] = 8;
     cpu.regs.flags.c = true;
@@ -140,10 +137,10 @@ fn test_ADC_An() {
     let cartridge = build_cartridge(vec![
         0x3E, 0x12, // LD A, $12
         0xCE, 0x09, // ADC A, $09
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     cpu.regs.flags.c = true;
 
@@ -169,10 +166,10 @@ fn test_SUB_AHL() {
         0x26, 0xC0, // LD H, $C0
         0x2E, 0x00, // LD L, $00
         0x96, // SUB A, (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     mmu[0xC000] = 8;
     cpu.regs.flags.c = true;
@@ -187,10 +184,10 @@ fn test_SUB_An() {
     let cartridge = build_cartridge(vec![
         0x3E, 0x12, // LD A, $12
         0xD6, 0x09, // SUB A, $09
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     cpu.regs.flags.c = true;
 
@@ -216,10 +213,10 @@ fn test_SBC_AHL() {
         0x26, 0xC0, // LD H, $C0
         0x2E, 0x00, // LD L, $00
         0x9E, // SBC A, (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     mmu[0xC000] = 8;
     cpu.regs.flags.c = true;
@@ -234,10 +231,10 @@ fn test_SBC_An() {
     let cartridge = build_cartridge(vec![
         0x3E, 0x12, // LD A, $12
         0xDE, 0x09, // SBC A, $09
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
     cpu.regs.flags.c = true;
 
@@ -259,11 +256,11 @@ macro_rules! logic_tests {
                 0x11, 0x44, 0x33, // LD DE, $3344
                 0x21, 0x66, 0x55, // LD HL, $5566
                 opcode, // <op> A,x
-                0x10, 0x00, // STOP
-                0x10, 0x00, // STOP
+                0x18, 0x00, // JR +0, or operand $18 for <op> A,n
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0x5566] = 0xFF;
@@ -285,7 +282,7 @@ logic_tests! {
     test_AND_AH: (0xA4, 0b00000000),
     test_AND_AL: (0xA5, 0b00100010),
     test_AND_AHL: (0xA6, 0b10101010),
-    test_AND_An: (0xE6, 0b00000000),
+    test_AND_An: (0xE6, 0b00001000),
 }
 
 logic_tests! {
@@ -309,7 +306,7 @@ logic_tests! {
     test_XOR_AH: (0xAC, 0b11111111),
     test_XOR_AL: (0xAD, 0b11001100),
     test_XOR_AHL: (0xAE, 0b01010101),
-    test_XOR_An: (0xEE, 0b10111010),
+    test_XOR_An: (0xEE, 0b10110010),
 }
 
 macro_rules! cp_tests {
@@ -325,11 +322,11 @@ macro_rules! cp_tests {
                 0x11, 0x44, 0x33, // LD DE, $3344
                 0x21, 0x66, 0x55, // LD HL, $5566
                 opcode, // CP A,x
-                0x10, 0x00, // STOP
-                0x10, 0x00, // STOP
+                0x18, 0x00, // JR +0, or operand $18 for <op> A,n
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0x5566] = 0xFF;
@@ -370,10 +367,10 @@ macro_rules! inc_tests {
                 0x11, 0x44, 0x33, // LD DE, $3344
                 0x21, 0x66, 0x55, // LD HL, $5566
                 opcode, // INC reg
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.run(&mut mmu);
@@ -397,19 +394,19 @@ inc_tests! {
 #[test]
 fn test_INC_HL() {
     let cartridge = build_cartridge(vec![
-        0x21, 0x66, 0x55, // LD HL, $5566
+        0x21, 0x66, 0xC5, // LD HL, $C566
         0x34, // INC (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
-    mmu[0x5566] = 0xFF;
+    mmu[0xC566] = 0xFF;
 
     cpu.run(&mut mmu);
 
-    assert_eq!(0x00, mmu[0x5566]);
+    assert_eq!(0x00, mmu[0xC566]);
 }
 
 inc_tests! {
@@ -425,17 +422,17 @@ inc_tests! {
 #[test]
 fn test_DEC_HL() {
     let cartridge = build_cartridge(vec![
-        0x21, 0x66, 0x55, // LD HL, $5566
+        0x21, 0x66, 0xC5, // LD HL, $C566
         0x35, // DEC (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
-    mmu[0x5566] = 0xFF;
+    mmu[0xC566] = 0xFF;
 
     cpu.run(&mut mmu);
 
-    assert_eq!(0xFE, mmu[0x5566]);
+    assert_eq!(0xFE, mmu[0xC566]);
 }
@@ -0,0 +1,98 @@
+use ruboy::alu::{add16, add8, add_sp, dec8, inc8, sub8, Alu16, Alu8};
+
+/// Reference model: a ripple-carry adder returning the sum and the carry out of every bit.
+fn ripple_add(a: u32, b: u32, carry: bool, bits: u32) -> (u32, Vec<bool>) {
+    let mut sum = 0;
+    let mut carries = Vec::new();
+    let mut carry = carry;
+
+    for bit in 0..bits {
+        let (x, y) = ((a >> bit) & 1 == 1, (b >> bit) & 1 == 1);
+        sum |= ((x ^ y ^ carry) as u32) << bit;
+        carry = (x && y) || (carry && (x ^ y));
+        carries.push(carry);
+    }
+
+    (sum, carries)
+}
+
+fn reference_add8(a: u8, b: u8, carry: bool) -> Alu8 {
+    let (sum, carries) = ripple_add(a as u32, b as u32, carry, 8);
+    Alu8 { value: sum as u8, z: sum == 0, n: false, h: carries[3], c: carries[7] }
+}
+
+/// Subtraction is the addition of the complement, a borrow being the absence of carry.
+fn reference_sub8(a: u8, b: u8, carry: bool) -> Alu8 {
+    let (sum, carries) = ripple_add(a as u32, !b as u32, !carry, 8);
+    Alu8 { value: sum as u8, z: sum == 0, n: true, h: !carries[3], c: !carries[7] }
+}
+
+#[test]
+fn test_add8_exhaustive() {
+    for a in 0..=0xFFu8 {
+        for b in 0..=0xFFu8 {
+            for carry in [false, true] {
+                assert_eq!(reference_add8(a, b, carry), add8(a, b, carry), "{:02X} + {:02X} + {}", a, b, carry);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sub8_exhaustive() {
+    for a in 0..=0xFFu8 {
+        for b in 0..=0xFFu8 {
+            for carry in [false, true] {
+                assert_eq!(reference_sub8(a, b, carry), sub8(a, b, carry), "{:02X} - {:02X} - {}", a, b, carry);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_inc_dec_exhaustive() {
+    for a in 0..=0xFFu8 {
+        assert_eq!(Alu8 { c: false, ..reference_add8(a, 1, false) }, inc8(a));
+        assert_eq!(Alu8 { c: false, ..reference_sub8(a, 1, false) }, dec8(a));
+    }
+}
+
+#[test]
+fn test_add8_flags() {
+    assert_eq!(Alu8 { value: 0x00, z: true, n: false, h: true, c: true }, add8(0xFF, 0x01, false));
+    assert_eq!(Alu8 { value: 0x10, z: false, n: false, h: true, c: false }, add8(0x0F, 0x00, true));
+    assert_eq!(Alu8 { value: 0xFF, z: false, n: true, h: true, c: true }, sub8(0x00, 0x00, true));
+    assert_eq!(Alu8 { value: 0x00, z: true, n: true, h: false, c: false }, sub8(0x3E, 0x3E, false));
+}
+
+#[test]
+fn test_add16() {
+    // Every combination of the nibbles around the bit 11 and bit 15 carries
+    let values: Vec<u16> = (0..=0xFFu16)
+        .map(|x| (x & 0xF0) << 8 | (x & 0x0F) << 8 | x.wrapping_mul(0x35) & 0xFF)
+        .collect();
+
+    for &a in &values {
+        for &b in &values {
+            let (sum, carries) = ripple_add(a as u32, b as u32, false, 16);
+            let expected = Alu16 { value: sum as u16, h: carries[11], c: carries[15] };
+            assert_eq!(expected, add16(a, b), "{:04X} + {:04X}", a, b);
+        }
+    }
+
+    assert_eq!(Alu16 { value: 0x1000, h: true, c: false }, add16(0x0FFF, 0x0001));
+    assert_eq!(Alu16 { value: 0x0000, h: true, c: true }, add16(0xFFFF, 0x0001));
+}
+
+#[test]
+fn test_add_sp() {
+    for sp in [0x0000, 0x000F, 0x00F0, 0x00FF, 0x0100, 0x7FFF, 0x8000, 0xC0DE, 0xFFF8, 0xFFFF] {
+        for offset in 0..=0xFFu8 {
+            let (_, carries) = ripple_add(sp as u32 & 0xFF, offset as u32, false, 8);
+            let value = (sp as i32 + offset as i8 as i32) as u16;
+
+            let expected = Alu16 { value, h: carries[3], c: carries[7] };
+            assert_eq!(expected, add_sp(sp, offset), "{:04X} + {:02X}", sp, offset);
+        }
+    }
+}
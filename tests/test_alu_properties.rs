@@ -0,0 +1,264 @@
+//! Differential tests of the ALU instructions, executed by the CPU and compared with a
+//! reference model written from the documented behaviour, over random registers and flags.
+
+use proptest::prelude::*;
+
+use ruboy::bus::FlatBus;
+use ruboy::cpu::{self, CpuState, StepResult};
+
+const PROGRAM: u16 = 0x0100;
+
+const Z: u8 = 0x80;
+const N: u8 = 0x40;
+const H: u8 = 0x20;
+const C: u8 = 0x10;
+
+/// Index of the `(HL)` operand in the 8-bit operand encoding: B, C, D, E, H, L, (HL), A
+const HL_INDIRECT: u8 = 6;
+
+#[derive(Clone, Debug)]
+struct Input {
+    state: CpuState,
+    /// Byte at HL
+    memory: u8,
+}
+
+prop_compose! {
+    fn inputs()(regs in any::<[u8; 7]>(), f in any::<u8>(), sp in any::<u16>(), memory in any::<u8>()) -> Input {
+        let [a, b, c, d, e, h, l] = regs;
+        let state = CpuState { a, f: f & 0xF0, b, c, d, e, h, l, sp, pc: PROGRAM, ..CpuState::default() };
+        Input { state, memory }
+    }
+}
+
+/// Executes a single instruction and returns the registers and the byte at HL afterwards.
+fn execute(program: &[u8], input: &Input) -> (CpuState, u8) {
+    let hl = input.state.hl();
+    assert!(!(PROGRAM..PROGRAM + 3).contains(&hl));
+
+    let mut bus = FlatBus::new();
+    bus.load(PROGRAM, program);
+    bus[hl] = input.memory;
+
+    let mut cpu = cpu::init_cpu();
+    cpu.set_state(input.state);
+    assert_eq!(StepResult::Continue, cpu.step(&mut bus));
+
+    (cpu.state(), bus[hl])
+}
+
+fn flags(z: bool, n: bool, h: bool, c: bool) -> u8 {
+    [(z, Z), (n, N), (h, H), (c, C)].iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |f, (_, mask)| f | mask)
+}
+
+fn operand(input: &Input, index: u8) -> u8 {
+    let s = &input.state;
+    [s.b, s.c, s.d, s.e, s.h, s.l, input.memory, s.a][index as usize]
+}
+
+/// Stores `value` in the given 8-bit operand of the expected state.
+fn set_operand(expected: &mut (CpuState, u8), index: u8, value: u8) {
+    let s = &mut expected.0;
+    let target = match index {
+        0 => &mut s.b,
+        1 => &mut s.c,
+        2 => &mut s.d,
+        3 => &mut s.e,
+        4 => &mut s.h,
+        5 => &mut s.l,
+        HL_INDIRECT => &mut expected.1,
+        _ => &mut s.a,
+    };
+    *target = value;
+}
+
+/// Registers and byte at HL expected after an instruction of `length` bytes which doesn't
+/// change anything else.
+fn unchanged(input: &Input, length: u16) -> (CpuState, u8) {
+    (CpuState { pc: PROGRAM + length, ..input.state }, input.memory)
+}
+
+/// ADD, ADC, SUB, SBC, AND, XOR, OR and CP, in opcode order. Returns A and F.
+fn reference_alu8(op: u8, a: u8, n: u8, f: u8) -> (u8, u8) {
+    let carry = (op == 1 || op == 3) && f & C != 0;
+    let (a, n, cin) = (a as i32, n as i32, carry as i32);
+
+    let (result, flags) = match op {
+        0 | 1 => {
+            let r = a + n + cin;
+            (r, flags(r & 0xFF == 0, false, (a ^ n ^ r) & 0x10 != 0, r > 0xFF))
+        }
+        2 | 3 | 7 => {
+            let r = a - n - cin;
+            (r, flags(r & 0xFF == 0, true, (a ^ n ^ r) & 0x10 != 0, r < 0))
+        }
+        4 => (a & n, flags(a & n == 0, false, true, false)),
+        5 => (a ^ n, flags(a ^ n == 0, false, false, false)),
+        _ => (a | n, flags(a | n == 0, false, false, false)),
+    };
+
+    // CP only sets the flags
+    if op == 7 { (a as u8, flags) } else { (result as u8, flags) }
+}
+
+/// RLC, RRC, RL, RR, SLA, SRA, SWAP and SRL, in opcode order. Returns the result and the
+/// carry.
+fn reference_shift(op: u8, value: u8, carry: bool) -> (u8, bool) {
+    let bit7 = value & 0x80 != 0;
+    let bit0 = value & 0x01 != 0;
+
+    match op {
+        0 => (value << 1 | bit7 as u8, bit7),
+        1 => (value >> 1 | (bit0 as u8) << 7, bit0),
+        2 => (value << 1 | carry as u8, bit7),
+        3 => (value >> 1 | (carry as u8) << 7, bit0),
+        4 => (value << 1, bit7),
+        5 => (value >> 1 | value & 0x80, bit0),
+        6 => ((value & 0x0F) << 4 | (value & 0xF0) >> 4, false),
+        _ => (value >> 1, bit0),
+    }
+}
+
+/// `SP + e8`, with the flags of ADD SP,e8 and LD HL,SP+e8.
+fn reference_sp_offset(sp: u16, offset: u8) -> (u16, u8) {
+    let e = offset as i8 as i32;
+    let r = sp as i32 + e;
+    let carries = sp as i32 ^ e ^ r;
+
+    (r as u16, flags(false, false, carries & 0x10 != 0, carries & 0x100 != 0))
+}
+
+proptest! {
+    #[test]
+    fn test_alu8(op in 0..8u8, index in 0..9u8, immediate in any::<u8>(), input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        // Index 8 is the d8 operand
+        let (program, n) = if index == 8 {
+            (vec![0xC6 + op * 8, immediate], immediate)
+        } else {
+            (vec![0x80 + op * 8 + index], operand(&input, index))
+        };
+
+        let mut expected = unchanged(&input, program.len() as u16);
+        (expected.0.a, expected.0.f) = reference_alu8(op, input.state.a, n, input.state.f);
+
+        prop_assert_eq!(expected, execute(&program, &input), "{:02X?}", program);
+    }
+
+    #[test]
+    fn test_inc_dec(dec in any::<bool>(), index in 0..8u8, input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        let program = [0x04 + index * 8 + dec as u8];
+        let value = operand(&input, index) as i32;
+        let result = if dec { value - 1 } else { value + 1 };
+
+        let mut expected = unchanged(&input, 1);
+        set_operand(&mut expected, index, result as u8);
+        let half_carry = (value ^ 1 ^ result) & 0x10 != 0;
+        expected.0.f = flags(result as u8 == 0, dec, half_carry, input.state.f & C != 0);
+
+        prop_assert_eq!(expected, execute(&program, &input), "{:02X?}", program);
+    }
+
+    #[test]
+    fn test_daa(input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        let f = input.state.f;
+        let mut a = input.state.a;
+        let mut carry = f & C != 0;
+
+        if f & N == 0 {
+            if carry || a > 0x99 {
+                a = a.wrapping_add(0x60);
+                carry = true;
+            }
+            if f & H != 0 || a & 0x0F > 0x09 {
+                a = a.wrapping_add(0x06);
+            }
+        } else {
+            if carry {
+                a = a.wrapping_sub(0x60);
+            }
+            if f & H != 0 {
+                a = a.wrapping_sub(0x06);
+            }
+        }
+
+        let mut expected = unchanged(&input, 1);
+        expected.0.a = a;
+        expected.0.f = flags(a == 0, f & N != 0, false, carry);
+
+        prop_assert_eq!(expected, execute(&[0x27], &input));
+    }
+
+    #[test]
+    fn test_add_hl(pair in 0..4u8, input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        let s = &input.state;
+        let hl = s.hl() as u32;
+        let n = [s.bc(), s.de(), s.hl(), s.sp][pair as usize] as u32;
+        let r = hl + n;
+
+        let mut expected = unchanged(&input, 1);
+        expected.0.set_hl(r as u16);
+        expected.0.f = s.f & Z | flags(false, false, (hl ^ n ^ r) & 0x1000 != 0, r > 0xFFFF);
+
+        prop_assert_eq!(expected, execute(&[0x09 + pair * 0x10], &input));
+    }
+
+    #[test]
+    fn test_add_sp(offset in any::<u8>(), input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        let mut expected = unchanged(&input, 2);
+        (expected.0.sp, expected.0.f) = reference_sp_offset(input.state.sp, offset);
+
+        prop_assert_eq!(expected, execute(&[0xE8, offset], &input));
+    }
+
+    #[test]
+    fn test_ld_hl_sp_offset(offset in any::<u8>(), input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        let mut expected = unchanged(&input, 2);
+        let (hl, f) = reference_sp_offset(input.state.sp, offset);
+        expected.0.set_hl(hl);
+        expected.0.f = f;
+
+        prop_assert_eq!(expected, execute(&[0xF8, offset], &input));
+    }
+
+    #[test]
+    fn test_rotate_a(op in 0..4u8, input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        // RLCA, RRCA, RLA and RRA work like their prefixed versions, but always clear Z
+        let (a, carry) = reference_shift(op, input.state.a, input.state.f & C != 0);
+
+        let mut expected = unchanged(&input, 1);
+        expected.0.a = a;
+        expected.0.f = flags(false, false, false, carry);
+
+        prop_assert_eq!(expected, execute(&[0x07 + op * 8], &input));
+    }
+
+    #[test]
+    fn test_prefixed_shift(op in 0..8u8, index in 0..8u8, input in inputs()) {
+        prop_assume!(!(PROGRAM..PROGRAM + 3).contains(&input.state.hl()));
+
+        let program = [0xCB, op * 8 + index];
+        let (result, carry) = reference_shift(op, operand(&input, index), input.state.f & C != 0);
+
+        let mut expected = unchanged(&input, 2);
+        set_operand(&mut expected, index, result);
+        expected.0.f = flags(result == 0, false, false, carry);
+
+        prop_assert_eq!(expected, execute(&program, &input), "{:02X?}", program);
+    }
+}
@@ -0,0 +1,229 @@
+use ruboy::asm;
+use ruboy::assembler::{assemble, Section, DEFAULT_ORIGIN};
+use ruboy::bus::FlatBus;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::{decode, disassemble};
+use ruboy::opcodes::RegisterId::{A, B, D};
+
+use crate::common::build_cartridge;
+
+mod common;
+
+#[test]
+fn test_disassembly_round_trip() {
+    for opcode in 0..=0xFFu8 {
+        for bytes in [[opcode, 0x12, 0x34], [0xCB, opcode, 0x00]] {
+            let Some(instr) = decode(bytes[0], bytes[1]) else {
+                continue;
+            };
+            let mut bytes = bytes[..instr.length() as usize].to_vec();
+            if opcode == 0x10 {
+                bytes[1] = 0x00; // STOP padding
+            }
+
+            let text = disassemble(&bytes).unwrap();
+            let program = assemble(&text).unwrap_or_else(|e| panic!("{}: {}", text, e));
+
+            assert_eq!(bytes, program.bytes(), "{}", text);
+        }
+    }
+}
+
+#[test]
+fn test_syntax() {
+    let program = asm!(
+        "  LD a , $F0 ; comment",
+        "  ld b, 0x10",
+        "  ld c, %101",
+        "  ld d, 0b11",
+        "  ld h, 0X1F",
+        "  ld l, 0B10",
+        "  ld e, 42",
+        "  ldh ($80), a",
+        "  ldh a, ($FF44)",
+        "  ld hl, sp-2",
+        "  add sp, +5",
+        "  sub b",
+        "  sub a, b",
+        "  add a, $12",
+        "  cp $12",
+        "  bit 7, h",
+        "  rst $38",
+        "  rst 08H",
+        "  stop",
+    );
+
+    assert_eq!(vec![
+        0x3E, 0xF0,
+        0x06, 0x10,
+        0x0E, 0x05,
+        0x16, 0x03,
+        0x26, 0x1F,
+        0x2E, 0x02,
+        0x1E, 42,
+        0xE0, 0x80,
+        0xF0, 0x44,
+        0xF8, 0xFE,
+        0xE8, 0x05,
+        0x90,
+        0x90,
+        0xC6, 0x12,
+        0xFE, 0x12,
+        0xCB, 0x7C,
+        0xFF,
+        0xCF,
+        0x10, 0x00,
+    ], program);
+}
+
+#[test]
+fn test_labels() {
+    let program = assemble(r#"
+        start:
+            jr forward          ; $0100
+            jp start + 1        ; $0102
+        .back: dec a            ; $0105
+            jr nz, .back        ; $0106
+        forward:
+            call start.back     ; $0108
+            ld hl, table        ; $010B
+            jr -2               ; $010E
+        table:
+            db 1, -1, "Hi;,"    ; $0110
+            dw table, $1234
+    "#).unwrap();
+
+    assert_eq!(Some(0x0100), program.label("start"));
+    assert_eq!(Some(0x0105), program.label("start.back"));
+    assert_eq!(Some(0x0108), program.label("forward"));
+    assert_eq!(Some(0x0110), program.label("table"));
+
+    assert_eq!(vec![
+        0x18, 0x06,
+        0xC3, 0x01, 0x01,
+        0x3D,
+        0x20, 0xFD,
+        0xCD, 0x05, 0x01,
+        0x21, 0x10, 0x01,
+        0x18, 0xFE,
+        0x01, 0xFF, b'H', b'i', b';', b',',
+        0x10, 0x01, 0x34, 0x12,
+    ], program.bytes());
+}
+
+#[test]
+fn test_local_labels() {
+    let program = assemble(r#"
+        first:
+        .loop: dec a            ; $0100
+            jr nz, .loop        ; $0101
+        second:
+        .loop: dec b            ; $0103
+            jr nz, .loop        ; $0104
+            jp first.loop       ; $0106
+    "#).unwrap();
+
+    assert_eq!(Some(0x0100), program.label("first.loop"));
+    assert_eq!(Some(0x0103), program.label("second.loop"));
+
+    assert_eq!(vec![
+        0x3D,
+        0x20, 0xFD,
+        0x05,
+        0x20, 0xFD,
+        0xC3, 0x00, 0x01,
+    ], program.bytes());
+}
+
+#[test]
+fn test_sections() {
+    let program = assemble(r#"
+        jp main
+        SECTION "data", $C000
+        counter: db 0
+        SECTION "main", $0150
+        main: ld hl, counter
+    "#).unwrap();
+
+    assert_eq!(&[
+        Section { name: String::new(), address: DEFAULT_ORIGIN, bytes: vec![0xC3, 0x50, 0x01] },
+        Section { name: "data".to_owned(), address: 0xC000, bytes: vec![0x00] },
+        Section { name: "main".to_owned(), address: 0x0150, bytes: vec![0x21, 0x00, 0xC0] },
+    ], program.sections());
+
+    let mut bus = FlatBus::new();
+    program.load(&mut bus);
+    assert_eq!(0xC3, bus[0x0100]);
+    assert_eq!(0x21, bus[0x0150]);
+
+    let rom = assemble("jp $0150\nSECTION \"main\", $0150\nnop").unwrap();
+    assert_eq!(0x51, rom.bytes().len());
+    assert_eq!(0x8000, rom.cartridge().content.len());
+    assert_eq!(0xC3, rom.cartridge().content[0x0100]);
+}
+
+macro_rules! error_tests {
+    ($($name:ident: $value:expr,)*) => {
+    $(
+        #[test]
+        fn $name() {
+            let (source, line, message) = $value;
+
+            let error = assemble(source).unwrap_err();
+
+            assert_eq!(line, error.line);
+            assert_eq!(message, error.message);
+        }
+    )*
+    }
+}
+
+error_tests! {
+    test_unknown_instruction: ("nop\nfoo a", 2, "Unknown instruction 'FOO'"),
+    test_invalid_operands: ("ld (bc), b", 1, "Invalid operands for LD: '(bc), b'"),
+    test_unknown_label: ("nop\n\njp nowhere", 3, "Unknown label 'nowhere'"),
+    test_register_label: ("hl: nop", 1, "'hl' is a register or condition, not a label"),
+    test_duplicate_label: ("a1: nop\na1: nop", 2, "Label 'a1' is already defined"),
+    test_duplicate_local_label: ("a1:\n.l: nop\n.l: nop", 3, "Label 'a1.l' is already defined"),
+    test_byte_range: ("ld a, 256", 1, "Value 256 is out of range"),
+    test_high_page: ("ldh a, ($FE00)", 1, "Address $FE00 isn't in $FF00-$FFFF"),
+    test_jump_range: ("jr far\ndb \"0123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789\"\nfar: nop", 1, "Jump offset 130 is out of range"),
+    test_section_overlap: ("SECTION \"a\", $0100\nnop\nnop\nSECTION \"b\", $0101\nnop", 4, "Section \"b\" overlaps section \"a\""),
+}
+
+#[test]
+fn test_run() {
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(asm!(
+        "    ld a, 0",
+        "    ld b, 5",
+        "loop:",
+        "    add a, 3",
+        "    dec b",
+        "    jr nz, loop",
+        format!("    db ${:02X}", common::EXIT),
+    )));
+
+    cpu.run(&mut mmu);
+
+    assert_eq!(15, cpu.regs[A]);
+    assert_eq!(0, cpu.regs[B]);
+}
+
+#[test]
+fn test_run_label_address() {
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(asm!(
+        "    ld hl, target",
+        "    push hl",
+        "    ret",
+        "    jr -2",
+        "target:",
+        "    ld d, $42",
+        format!("    db ${:02X}", common::EXIT),
+    )));
+
+    cpu.run(&mut mmu);
+
+    assert_eq!(0x42, cpu.regs[D]);
+}
@@ -1,10 +1,8 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -24,10 +22,10 @@ macro_rules! bit_tests {
                 0x26, 0xA5, // LD H, $A5
                 0x2E, 0x96, // LD L, $96
                 0xCB, opcode, // <op> x
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0xA596] = 0b01010101;
@@ -35,8 +33,8 @@ macro_rules! bit_tests {
             cpu.run(&mut mmu);
 
             assert_eq!(expected_z, cpu.regs.flags.z);
-            assert_eq!(false, cpu.regs.flags.n);
-            assert_eq!(true, cpu.regs.flags.h);
+            assert!(!cpu.regs.flags.n);
+            assert!(cpu.regs.flags.h);
         }
     )*
     }
@@ -0,0 +1,128 @@
+use ruboy::bus::{Bus, FlatBus};
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::cpu::StepResult;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::EXIT;
+
+mod common;
+
+#[test]
+fn test_flat_bus() {
+    let mut bus = FlatBus::new();
+    bus.load(0x0100, &[
+        0x3E, 0x42, // LD A, $42
+        0xEA, 0x00, 0xE0, // LD ($E000), A
+        0xE0, 0x04, // LDH ($04), A
+        0xFA, 0x00, 0xC0, // LD A, ($C000)
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    cpu.run(&mut bus);
+
+    // Echo RAM and I/O registers are plain memory
+    assert_eq!(0x42, bus[0xE000]);
+    assert_eq!(0x00, bus[0xC000]);
+    assert_eq!(0x42, bus[0xFF04]);
+    assert_eq!(0x00, cpu.regs[A]);
+
+    assert_eq!(52, cpu.cycles());
+    assert_eq!(cpu.cycles(), bus.cycles());
+}
+
+#[test]
+fn test_rom_is_writable() {
+    let mut bus = FlatBus::new();
+    bus.load(0x0100, &[
+        0x3E, 0x42, // LD A, $42
+        0xEA, 0x00, 0x00, // LD ($0000), A
+    ]);
+
+    let mut cpu = cpu::init_cpu();
+    cpu.step(&mut bus);
+    cpu.step(&mut bus);
+
+    assert_eq!(0x42, bus.peek(0x0000));
+}
+
+#[test]
+fn test_mmu_rom_is_read_only() {
+    let mut mmu = Mmu::new(Cartridge { content: vec![0; 0x4000] });
+    mmu[0x0100] = 0x3E; // LD A, $42
+    mmu[0x0101] = 0x42;
+    mmu[0x0102] = 0xEA; // LD ($0000), A
+    mmu[0x0103] = 0x00;
+    mmu[0x0104] = 0x00;
+
+    let mut cpu = cpu::init_cpu();
+    cpu.step(&mut mmu);
+    cpu.step(&mut mmu);
+
+    assert_eq!(0x00, mmu.peek(0x0000));
+    mmu.poke(0x0000, 0x42);
+    assert_eq!(0x42, mmu.peek(0x0000));
+
+    // Past the end of the cartridge
+    assert_eq!(0xFF, mmu.peek(0x4000));
+    mmu.poke(0x4000, 0x00);
+    assert_eq!(0xFF, mmu.peek(0x4000));
+
+    // Read-modify-writes don't see what was written to other ignored addresses
+    mmu[0xFEA0] = 0x12;
+    let byte = &mut mmu[0x4001];
+    assert_eq!(0xFF, *byte);
+    *byte &= 0x0F;
+    let byte = &mut mmu[0xFEA1];
+    assert_eq!(0xFF, *byte);
+}
+
+/// Runs the same program on any bus.
+fn run_program(bus: &mut impl Bus) -> u8 {
+    for (i, &byte) in [0x3E, 0x05, 0x3D, 0x3D].iter().enumerate() {
+        bus.poke(0x0100 + i as u16, byte); // LD A, $05; DEC A; DEC A
+    }
+
+    let mut cpu = cpu::init_cpu();
+    for _ in 0..3 {
+        assert_eq!(StepResult::Continue, cpu.step(bus));
+    }
+    cpu.regs[A]
+}
+
+#[test]
+fn test_generic_bus() {
+    assert_eq!(0x03, run_program(&mut FlatBus::new()));
+    assert_eq!(0x03, run_program(&mut Mmu::new(Cartridge { content: vec![0; 0x8000] })));
+}
+
+#[test]
+fn test_address_wrap_around() {
+    let mut bus = FlatBus::new();
+    bus[0x7FFF] = 0x00; // NOP
+    bus[0xFFFE] = 0xC3; // JP $0150, with its address wrapping around to 0x0000
+    bus[0xFFFF] = 0x50;
+    bus[0x0000] = 0x01;
+    bus.load(0x0150, &[
+        0xC5, // PUSH BC
+        0xF1, // POP AF
+    ]);
+
+    let mut cpu = cpu::init_cpu();
+    cpu.regs.pc = 0x7FFF;
+    cpu.step(&mut bus);
+    assert_eq!(0x8000, cpu.regs.pc);
+
+    cpu.regs.pc = 0xFFFE;
+    cpu.step(&mut bus);
+    assert_eq!(0x0150, cpu.regs.pc);
+
+    // The stack wraps around too
+    cpu.regs.sp = 0x0001;
+    cpu.step(&mut bus);
+    assert_eq!(0xFFFF, cpu.regs.sp);
+    cpu.step(&mut bus);
+    assert_eq!(0x0001, cpu.regs.sp);
+}
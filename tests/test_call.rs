@@ -1,11 +1,9 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::D;
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -16,18 +14,17 @@ fn test_CALL_nn() {
         0x18, 0xF2, // JR -2 (infinite loop)
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0x2000] = 0x16; // LD D, $42
     mmu[0x2001] = 0x42;
-    mmu[0x2002] = 0x10; // STOP
-    mmu[0x2003] = 0x00;
+    mmu[0x2002] = EXIT;
 
     cpu.run(&mut mmu);
 
     assert_eq!(0x42, cpu.regs[D]);
-    assert_eq!(0x2003, cpu.regs.pc);
+    assert_eq!(0x2002, cpu.regs.pc);
     assert_eq!(0x03, mmu[0xFFFC]);
     assert_eq!(0x01, mmu[0xFFFD]);
 }
@@ -42,17 +39,16 @@ macro_rules! call_tests {
             let cartridge = build_cartridge(vec![
                 opcode, 0x00, 0x20, // CALL $2000
                 0x16, 0x69, // LD D, $69
-                0x10, 0x00 // STOP
+                EXIT
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0x2000] = 0x16; // LD D, $42
             mmu[0x2001] = 0x42;
-            mmu[0x2002] = 0x10; // STOP
-            mmu[0x2003] = 0x00;
-
+            mmu[0x2002] = EXIT;
+        
             cpu.regs.flags.z = z;
             cpu.regs.flags.c = c;
             cpu.regs[D] = 0x00;
@@ -0,0 +1,27 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+
+use ruboy::cartridge::{Cartridge, MIN_ROM_SIZE};
+
+#[test]
+fn test_load() {
+    let path = env::temp_dir().join(format!("ruboy-cartridge-{}.gb", std::process::id()));
+    let path_str = path.to_string_lossy().into_owned();
+
+    fs::write(&path, vec![0; MIN_ROM_SIZE - 1]).unwrap();
+    assert_eq!(ErrorKind::InvalidData, Cartridge::load(&path_str).err().unwrap().kind());
+
+    let mut rom = vec![0; MIN_ROM_SIZE];
+    rom[0x0134..0x0138].copy_from_slice(b"TEST");
+    fs::write(&path, rom).unwrap();
+    assert_eq!("TEST\0\0\0\0\0\0\0\0\0\0", Cartridge::load(&path_str).unwrap().name());
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(ErrorKind::NotFound, Cartridge::load(&path_str).err().unwrap().kind());
+}
+
+#[test]
+fn test_name_of_short_rom() {
+    assert_eq!("", Cartridge { content: vec![0; 0x100] }.name());
+}
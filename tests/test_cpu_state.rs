@@ -0,0 +1,149 @@
+use ruboy::cpu;
+use ruboy::cpu::{Cpu, CpuState, Flag, StepResult};
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::{A, B, H, L};
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+#[test]
+fn test_post_boot_state() {
+    assert_eq!(CpuState {
+        a: 0x01,
+        f: 0x00,
+        b: 0xFF,
+        c: 0x13,
+        d: 0x00,
+        e: 0xC1,
+        h: 0x84,
+        l: 0x03,
+        sp: 0xFFFE,
+        pc: 0x0100,
+        ime: false,
+        halted: false,
+        stopped: false,
+    }, cpu::init_cpu().state());
+}
+
+#[test]
+fn test_register_pairs_and_flags() {
+    let mut state = CpuState::default();
+
+    state.set_af(0x12FF);
+    state.set_bc(0x3456);
+    state.set_de(0x789A);
+    state.set_hl(0xBCDE);
+    assert_eq!((0x12, 0xF0, 0x34, 0x56), (state.a, state.f, state.b, state.c));
+    assert_eq!((0x78, 0x9A, 0xBC, 0xDE), (state.d, state.e, state.h, state.l));
+    assert_eq!((0x12F0, 0x3456, 0x789A, 0xBCDE), (state.af(), state.bc(), state.de(), state.hl()));
+
+    state.set_flag(Flag::N, false);
+    state.set_flag(Flag::C, false);
+    assert_eq!(0xA0, state.f);
+    assert!(state.flag(Flag::Z));
+    assert!(!state.flag(Flag::N));
+    assert!(state.flag(Flag::H));
+    assert!(!state.flag(Flag::C));
+
+    // The lower nibble of F doesn't exist
+    state.f = 0x0F;
+    assert_eq!(0x1200, state.af());
+}
+
+#[test]
+fn test_set_state() {
+    let state = CpuState {
+        a: 0x12,
+        f: 0x5F,
+        h: 0xC0,
+        l: 0x00,
+        sp: 0xDFF0,
+        pc: 0x0100,
+        ime: true,
+        ..CpuState::default()
+    };
+
+    let mut cpu = Cpu::from(state);
+    assert_eq!(0x12, cpu.regs[A]);
+    assert_eq!(0xC0, cpu.regs[H]);
+    assert_eq!(0x00, cpu.regs[L]);
+    assert_eq!(0x50, cpu.regs.flags.get_f());
+    assert_eq!(CpuState { f: 0x50, ..state }, CpuState::from(&cpu));
+
+    cpu.regs[B] = 0x42;
+    cpu.set_state(state);
+    assert_eq!(0x00, cpu.regs[B]);
+}
+
+#[test]
+fn test_ime() {
+    let cartridge = build_cartridge(vec![
+        0xFB, // EI
+        0x00, // NOP
+        0xF3, // DI
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    // EI takes effect after the next instruction
+    cpu.step(&mut mmu);
+    assert!(!cpu.state().ime);
+    cpu.step(&mut mmu);
+    assert!(cpu.state().ime);
+
+    cpu.run(&mut mmu);
+    assert!(!cpu.state().ime);
+}
+
+#[test]
+fn test_ei_di() {
+    let cartridge = build_cartridge(vec![
+        0xFB, // EI
+        0xF3, // DI
+        0x00, // NOP
+        0xFB, // EI
+        0xFB, // EI
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    // DI right after EI cancels it
+    for _ in 0..3 {
+        cpu.step(&mut mmu);
+        assert!(!cpu.state().ime);
+    }
+
+    cpu.step(&mut mmu);
+    assert!(!cpu.state().ime);
+    cpu.step(&mut mmu);
+    assert!(cpu.state().ime);
+}
+
+#[test]
+fn test_halted_state() {
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(vec![0x3E, 0x42])); // LD A, $42
+    mmu[0xFF0F] = 0x00;
+    mmu[0xFFFF] = 0x04;
+
+    cpu.set_state(CpuState { halted: true, ..cpu.state() });
+
+    // Waits for an enabled interrupt to be requested
+    for _ in 0..3 {
+        assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    }
+    assert!(cpu.state().halted);
+    assert_eq!(0x0100, cpu.regs.pc);
+
+    mmu[0xFF0F] = 0x04;
+    cpu.step(&mut mmu);
+    assert!(!cpu.state().halted);
+
+    cpu.step(&mut mmu);
+    assert_eq!(0x42, cpu.regs[A]);
+}
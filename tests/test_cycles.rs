@@ -0,0 +1,57 @@
+use ruboy::memory::Mmu;
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+/// Runs `program` and returns the cycles it took.
+fn cycles(program: Vec<u8>) -> u64 {
+    let mut program = program;
+    program.push(EXIT);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program));
+    cpu.run(&mut mmu);
+
+    cpu.cycles()
+}
+
+#[test]
+fn test_fixed_cycles() {
+    assert_eq!(4, cycles(vec![0x00])); // NOP
+    assert_eq!(8, cycles(vec![0x3E, 0x42])); // LD A, $42
+    assert_eq!(12, cycles(vec![0x01, 0x34, 0x12])); // LD BC, $1234
+    assert_eq!(16, cycles(vec![0xEA, 0x00, 0xC0])); // LD ($C000), A
+    assert_eq!(8, cycles(vec![0xCB, 0x37])); // SWAP A
+    assert_eq!(24, cycles(vec![0x21, 0x00, 0xC0, 0xCB, 0x46])); // LD HL, $C000; BIT 0, (HL)
+}
+
+#[test]
+fn test_conditional_jumps() {
+    // Z is clear after init_cpu
+    assert_eq!(12, cycles(vec![0x20, 0x00])); // JR NZ, +0
+    assert_eq!(8, cycles(vec![0x28, 0x00])); // JR Z, +0
+    assert_eq!(16, cycles(vec![0xC2, 0x03, 0x01])); // JP NZ, $0103
+    assert_eq!(12, cycles(vec![0xCA, 0x03, 0x01])); // JP Z, $0103
+}
+
+#[test]
+fn test_conditional_calls_and_returns() {
+    let program = vec![
+        0xC4, 0x05, 0x01, // CALL NZ, $0105
+        0x18, 0x02, // JR +2
+        0xC0, // RET NZ
+        0xC8, // RET Z
+    ];
+
+    // CALL NZ taken (24), RET NZ taken (20), JR (12)
+    assert_eq!(56, cycles(program));
+
+    let program = vec![
+        0xCC, 0x05, 0x01, // CALL Z, $0105
+        0xC8, // RET Z
+    ];
+
+    // CALL Z not taken (12), RET Z not taken (8)
+    assert_eq!(20, cycles(program));
+}
@@ -2,12 +2,10 @@
 
 use std::ops::Range;
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::A;
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -20,10 +18,10 @@ macro_rules! daa_tests {
 
             let cartridge = build_cartridge(vec![
                 0x27, // DAA
-                0x10, 0x00 // STOP
+                EXIT
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.regs.flags.n = n;
@@ -115,10 +113,10 @@ fn assert_daa(n: bool, cy: bool, hi_bits: Range<u8>, h: bool, lo_bits: Range<u8>
         for lo in lo_bits.clone() {
             let cartridge = build_cartridge(vec![
                 0x27, // DAA
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             let a = (hi << 4) + lo;
@@ -0,0 +1,124 @@
+use ruboy::debugger::{CallFrame, Debugger, StopReason, WatchKind};
+use ruboy::memory::{AccessKind, Mmu};
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+#[test]
+fn test_step() {
+    let cartridge = build_cartridge(vec![
+        0x3E, 0x42, // LD A, $42
+        0x00, // NOP
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(StopReason::Step, debugger.step(&mut cpu, &mut mmu));
+    assert_eq!(0x0102, cpu.regs.pc);
+    assert_eq!(StopReason::Step, debugger.step(&mut cpu, &mut mmu));
+    assert_eq!(0x0103, cpu.regs.pc);
+    assert_eq!(StopReason::Stopped, debugger.step(&mut cpu, &mut mmu));
+}
+
+#[test]
+fn test_breakpoint() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        0x00, // NOP
+        0x00, // NOP
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(0x0102);
+
+    assert_eq!(StopReason::Breakpoint(0x0102), debugger.resume(&mut cpu, &mut mmu, None));
+    assert_eq!(0x0102, cpu.regs.pc);
+
+    assert_eq!(StopReason::Stopped, debugger.resume(&mut cpu, &mut mmu, None));
+}
+
+#[test]
+fn test_limit() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        0x00, // NOP
+        0x00, // NOP
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(StopReason::Limit, debugger.resume(&mut cpu, &mut mmu, Some(2)));
+    assert_eq!(0x0102, cpu.regs.pc);
+}
+
+#[test]
+fn test_watchpoints() {
+    let cartridge = build_cartridge(vec![
+        0x21, 0x00, 0xC0, // LD HL, $C000
+        0x3E, 0x42, // LD A, $42
+        0x77, // LD (HL), A
+        0x7E, // LD A, (HL)
+        0xEA, 0x01, 0xC0, // LD ($C001), A
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    let mut debugger = Debugger::new();
+    debugger.add_watchpoint(0xC000, WatchKind::Read);
+    debugger.add_watchpoint(0xC001, WatchKind::Write);
+
+    assert_eq!(StopReason::Watchpoint { address: 0xC000, access: AccessKind::Read, value: 0x42 },
+               debugger.resume(&mut cpu, &mut mmu, None));
+    assert_eq!(0x0107, cpu.regs.pc);
+
+    assert_eq!(StopReason::Watchpoint { address: 0xC001, access: AccessKind::Write, value: 0x42 },
+               debugger.resume(&mut cpu, &mut mmu, None));
+    assert_eq!(0x42, mmu[0xC001]);
+}
+
+#[test]
+fn test_call_stack() {
+    let mut program = vec![
+        0xCD, 0x00, 0x02, // CALL $0200
+        EXIT,
+    ];
+    program.resize(0x100, 0x00);
+    program.extend([
+        0xCD, 0x10, 0x02, // $0200: CALL $0210
+        0xC9, // RET
+    ]);
+    program.resize(0x110, 0x00);
+    program.extend([
+        0xC9, // $0210: RET
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program));
+    let mut debugger = Debugger::new();
+
+    debugger.step(&mut cpu, &mut mmu);
+    debugger.step(&mut cpu, &mut mmu);
+
+    assert_eq!(&[
+        CallFrame { call_site: 0x0100, target: 0x0200, return_address: 0x0103, sp: 0xFFFC },
+        CallFrame { call_site: 0x0200, target: 0x0210, return_address: 0x0203, sp: 0xFFFA },
+    ], debugger.call_stack());
+
+    debugger.step(&mut cpu, &mut mmu);
+    assert_eq!(1, debugger.call_stack().len());
+
+    debugger.step(&mut cpu, &mut mmu);
+    assert!(debugger.call_stack().is_empty());
+    assert_eq!(0x0103, cpu.regs.pc);
+}
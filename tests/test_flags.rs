@@ -1,4 +1,3 @@
-use ruboy;
 use ruboy::cpu;
 use ruboy::cpu::Flag;
 
@@ -0,0 +1,204 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+
+use ruboy::cpu::{StepResult, CYCLES_PER_FRAME};
+use ruboy::gameboy::{GameBoy, Model, CLOCK_RATE, SAMPLE_RATE};
+use ruboy::joypad::Button;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::build_cartridge;
+
+mod common;
+
+const P1: u16 = 0xFF00;
+const IF: u16 = 0xFF0F;
+
+/// Draws tile 1 in the top left corner, then loops forever
+fn tile_program() -> Vec<u8> {
+    vec![
+        0x3E, 0xFF, // LD A, $FF
+        0xEA, 0x10, 0x80, // LD ($8010), A
+        0x3E, 0x01, // LD A, $01
+        0xEA, 0x00, 0x98, // LD ($9800), A
+        0x18, 0xFE, // JR -2
+    ]
+}
+
+fn gameboy(program: Vec<u8>) -> GameBoy {
+    GameBoy::builder(build_cartridge(program)).build().unwrap()
+}
+
+#[test]
+fn test_run_frame() {
+    let mut gameboy = gameboy(tile_program());
+    assert_eq!(0, gameboy.framebuffer().pixel(0, 0));
+
+    // Games wait in JR -2 loops, they don't end the frame
+    assert_eq!(StepResult::Continue, gameboy.run_frame());
+    assert_eq!(CYCLES_PER_FRAME, gameboy.cpu().cycles());
+    assert_eq!(3, gameboy.framebuffer().pixel(0, 0));
+    assert_eq!(0, gameboy.framebuffer().pixel(8, 0));
+
+    // Frames stay aligned after single steps
+    gameboy.step();
+    gameboy.run_frame();
+    assert_eq!(2 * CYCLES_PER_FRAME, gameboy.cpu().cycles());
+}
+
+#[test]
+fn test_audio_samples() {
+    let mut gameboy = gameboy(tile_program());
+    assert!(gameboy.audio_samples().is_empty());
+
+    gameboy.run_frame();
+    let samples = gameboy.audio_samples();
+    assert_eq!(2 * (CYCLES_PER_FRAME * SAMPLE_RATE / CLOCK_RATE) as usize, samples.len());
+    assert!(samples.iter().all(|&sample| sample == 0));
+
+    assert!(gameboy.audio_samples().is_empty());
+}
+
+#[test]
+fn test_joypad() {
+    let mut gameboy = gameboy(vec![]);
+    gameboy.mmu_mut().write(IF, 0x00);
+
+    // Buttons selected
+    gameboy.mmu_mut().write(P1, 0x10);
+    assert_eq!(0xDF, gameboy.mmu()[P1]);
+
+    gameboy.press(Button::Up);
+    assert_eq!(0xDF, gameboy.mmu()[P1]);
+    assert_eq!(0x00, gameboy.mmu()[IF]);
+
+    gameboy.press(Button::Start);
+    assert_eq!(0xD7, gameboy.mmu()[P1]);
+    assert_eq!(0x10, gameboy.mmu()[IF]);
+
+    // Direction keys selected
+    gameboy.mmu_mut().write(P1, 0x20);
+    assert_eq!(0xEB, gameboy.mmu()[P1]);
+
+    gameboy.release(Button::Up);
+    gameboy.release(Button::Start);
+    assert_eq!(0xEF, gameboy.mmu()[P1]);
+
+    // Nothing selected
+    gameboy.press(Button::A);
+    gameboy.mmu_mut().write(P1, 0x30);
+    assert_eq!(0xFF, gameboy.mmu()[P1]);
+}
+
+#[test]
+fn test_press_wakes_up_from_stop() {
+    let mut gameboy = gameboy(vec![
+        0x3E, 0x20, // LD A, $20
+        0xE0, 0x00, // LDH ($00), A
+        0x10, 0x00, // STOP
+        0x3E, 0x42, // LD A, $42
+    ]);
+
+    for _ in 0..3 {
+        gameboy.step();
+    }
+    gameboy.run_frame();
+    assert!(gameboy.cpu().is_stopped());
+
+    gameboy.press(Button::Left);
+    gameboy.step();
+    gameboy.step();
+    assert_eq!(0x42, gameboy.cpu().regs[A]);
+}
+
+#[test]
+fn test_model() {
+    let cartridge = build_cartridge(vec![]);
+    assert_eq!(0x01, GameBoy::builder(cartridge.clone()).build().unwrap().cpu().regs[A]);
+
+    let gameboy = GameBoy::builder(cartridge).model(Model::Cgb).build().unwrap();
+    assert_eq!(Model::Cgb, gameboy.model());
+    assert_eq!(0x11, gameboy.cpu().regs[A]);
+}
+
+#[test]
+fn test_boot_rom() {
+    let mut boot_rom = vec![0x00; 0x100];
+    boot_rom[..4].copy_from_slice(&[
+        0x3E, 0x01, // LD A, $01
+        0xE0, 0x50, // LDH ($50), A
+    ]);
+
+    let mut gameboy = GameBoy::builder(build_cartridge(vec![0x3E, 0x42])) // LD A, $42
+        .boot_rom(boot_rom)
+        .build()
+        .unwrap();
+
+    assert_eq!(0x0000, gameboy.cpu().regs.pc);
+    assert_eq!(0x00, gameboy.cpu().regs[A]);
+    assert_eq!(0x3E, gameboy.mmu()[0x0000]);
+
+    // Disabling the boot ROM maps the cartridge back, then the NOPs run up to 0x0100
+    while gameboy.cpu().regs.pc != 0x0102 {
+        gameboy.step();
+    }
+    assert_eq!(0x00, gameboy.mmu()[0x0000]);
+    assert_eq!(0x42, gameboy.cpu().regs[A]);
+}
+
+#[test]
+fn test_boot_rom_size() {
+    let error = GameBoy::builder(build_cartridge(vec![]))
+        .boot_rom(vec![0x00; 0x80])
+        .build()
+        .err()
+        .unwrap();
+
+    assert_eq!(ErrorKind::InvalidInput, error.kind());
+}
+
+#[test]
+fn test_rng_seed() {
+    let ram = |seed: Option<u64>| {
+        let builder = GameBoy::builder(build_cartridge(vec![]));
+        let builder = match seed {
+            Some(seed) => builder.rng_seed(seed),
+            None => builder,
+        };
+        let gameboy = builder.build().unwrap();
+        (0xC000..0xE000).map(|address| gameboy.mmu()[address]).collect::<Vec<u8>>()
+    };
+
+    assert!(ram(None).iter().all(|&b| b == 0));
+    assert_eq!(ram(Some(1)), ram(Some(1)));
+    assert_ne!(ram(Some(1)), ram(Some(2)));
+    assert!(ram(Some(0)).iter().any(|&b| b != 0));
+}
+
+#[test]
+fn test_save_and_reset() {
+    let path = env::temp_dir().join(format!("ruboy-save-{}.sav", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut gameboy = GameBoy::builder(build_cartridge(tile_program()))
+        .save_path(&path)
+        .build()
+        .unwrap();
+    gameboy.run_frame();
+    gameboy.mmu_mut()[0xA000] = 0x42;
+    gameboy.mmu_mut()[0xC000] = 0x43;
+
+    // The cartridge RAM survives resets, not the work RAM
+    gameboy.reset();
+    assert_eq!(0, gameboy.cpu().cycles());
+    assert_eq!(0x0100, gameboy.cpu().regs.pc);
+    assert_eq!(0, gameboy.framebuffer().pixel(0, 0));
+    assert_eq!(0x42, gameboy.mmu()[0xA000]);
+    assert_eq!(0x00, gameboy.mmu()[0xC000]);
+
+    gameboy.save().unwrap();
+    let gameboy = GameBoy::builder(build_cartridge(vec![])).save_path(&path).build().unwrap();
+    assert_eq!(0x42, gameboy.mmu()[0xA000]);
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,149 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use ruboy::gdb;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    /// Sends a command and returns the reply, acknowledging it.
+    fn send(&mut self, command: &str) -> String {
+        let checksum = command.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        write!(self.stream, "${}#{:02x}", command, checksum).unwrap();
+
+        assert_eq!(b'+', self.read_byte(), "Packet '{}' was not acknowledged", command);
+        self.receive()
+    }
+
+    fn receive(&mut self) -> String {
+        while self.read_byte() != b'$' {}
+
+        let mut reply = Vec::new();
+        loop {
+            match self.read_byte() {
+                b'#' => break,
+                b => reply.push(b),
+            }
+        }
+        self.read_byte();
+        self.read_byte();
+        self.stream.write_all(b"+").unwrap();
+
+        String::from_utf8(reply).unwrap()
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let mut byte = [0u8];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+}
+
+/// Runs `script` as a gdb client against a stub serving `program`.
+fn debug_session<F>(program: Vec<u8>, script: F) -> Mmu
+where
+    F: FnOnce(&mut Client) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let client = thread::spawn(move || {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_nodelay(true).unwrap();
+        let mut client = Client { stream };
+        script(&mut client);
+        client.send("D");
+    });
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program));
+    cpu.regs[A] = 0x12;
+    cpu.regs.flags.set_f(0xB0);
+
+    gdb::serve(&listener, &mut cpu, &mut mmu).unwrap();
+    client.join().unwrap();
+
+    mmu
+}
+
+#[test]
+fn test_registers() {
+    debug_session(vec![EXIT], |client| {
+        assert_eq!("PacketSize=1000", client.send("qSupported:multiprocess+"));
+        assert_eq!("S05", client.send("?"));
+        // AF, BC, DE, HL, SP, PC, little-endian
+        assert_eq!("b012".to_owned() + "13ff" + "c100" + "0384" + "feff" + "0001", client.send("g"));
+
+        assert_eq!("OK", client.send("P1=3412"));
+        assert_eq!("3412", client.send("p1"));
+        assert_eq!("E01", client.send("p9"));
+
+        // Multibyte characters aren't sliced
+        assert_eq!("E01", client.send("éx"));
+        assert_eq!("E01", client.send(&format!("G0{}0", "é".repeat(11))));
+        assert_eq!("3412", client.send("p1"));
+    });
+}
+
+#[test]
+fn test_memory() {
+    let mmu = debug_session(vec![0x3E, 0x42, EXIT], |client| {
+        assert_eq!("3e42fd00", client.send("m100,4"));
+        assert_eq!("OK", client.send("Mc000,3:010203"));
+        assert_eq!("010203", client.send("mc000,3"));
+        assert_eq!("E01", client.send("Mc000,3:01"));
+    });
+
+    assert_eq!([0x01, 0x02, 0x03], [mmu[0xC000], mmu[0xC001], mmu[0xC002]]);
+}
+
+#[test]
+fn test_step_and_breakpoints() {
+    let program = vec![
+        0x3E, 0x42, // LD A, $42
+        0x00, // NOP
+        0x00, // NOP
+        EXIT,
+    ];
+
+    debug_session(program, |client| {
+        assert_eq!("S05", client.send("s"));
+        assert_eq!("0201", client.send("p5"));
+
+        assert_eq!("OK", client.send("Z0,103,1"));
+        assert_eq!("S05", client.send("c"));
+        assert_eq!("0301", client.send("p5"));
+
+        assert_eq!("OK", client.send("z0,103,1"));
+        assert_eq!("W00", client.send("c"));
+    });
+}
+
+#[test]
+fn test_watchpoints() {
+    let program = vec![
+        0x21, 0x00, 0xC0, // LD HL, $C000
+        0x3E, 0x42, // LD A, $42
+        0x77, // LD (HL), A
+        0x7E, // LD A, (HL)
+        EXIT,
+    ];
+
+    debug_session(program, |client| {
+        assert_eq!("OK", client.send("Z2,c000,1"));
+        assert_eq!("T05watch:c000;", client.send("c"));
+        assert_eq!("OK", client.send("z2,c000,1"));
+
+        assert_eq!("OK", client.send("Z3,c000,1"));
+        assert_eq!("T05rwatch:c000;", client.send("c"));
+    });
+}
@@ -0,0 +1,43 @@
+use ruboy::cpu::StepResult;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+const IF: u16 = 0xFF0F;
+const IE: u16 = 0xFFFF;
+
+#[test]
+fn test_halt() {
+    let cartridge = build_cartridge(vec![
+        0x76, // HALT
+        0x3E, 0x42, // LD A, $42
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    mmu[IE] = 0x04;
+    mmu[IF] = 0x00;
+
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert!(cpu.state().halted);
+    assert_eq!(0x0101, cpu.regs.pc);
+
+    // Requested interrupts which aren't enabled don't wake the CPU up
+    mmu[IF] = 0x01;
+    for _ in 0..100 {
+        assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    }
+    assert!(cpu.state().halted);
+    assert_eq!(0x0101, cpu.regs.pc);
+    assert_eq!(4 + 100 * 4, cpu.cycles());
+
+    mmu[IF] = 0x04;
+    cpu.run(&mut mmu);
+
+    assert!(!cpu.state().halted);
+    assert_eq!(0x42, cpu.regs[A]);
+}
@@ -0,0 +1,102 @@
+use ruboy::cpu;
+use ruboy::cpu::{IllegalOpcodePolicy, StepResult};
+use ruboy::debugger::{Debugger, StopReason};
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::build_cartridge;
+
+mod common;
+
+const ILLEGAL_OPCODES: [u8; 11] = [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD];
+
+fn program() -> Vec<u8> {
+    vec![
+        0x3E, 0x42, // LD A, $42
+        0xD3, // illegal
+        0x3E, 0x43, // LD A, $43
+    ]
+}
+
+#[test]
+fn test_lock() {
+    for opcode in ILLEGAL_OPCODES {
+        let mut cpu = cpu::init_cpu();
+        let mut mmu = Mmu::new(build_cartridge(vec![opcode]));
+
+        assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+        assert!(cpu.is_locked(), "{:02X}", opcode);
+    }
+
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program()));
+
+    cpu.run(&mut mmu);
+    assert!(cpu.is_locked());
+    assert_eq!(0x0102, cpu.regs.pc);
+
+    // The rest of the machine keeps running
+    mmu.write(0xFF04, 0x00);
+    for _ in 0..64 {
+        assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    }
+    assert_eq!(0x01, mmu[0xFF04]);
+    assert_eq!(0x0102, cpu.regs.pc);
+    assert_eq!(0x42, cpu.regs[A]);
+}
+
+#[test]
+fn test_error() {
+    for opcode in ILLEGAL_OPCODES {
+        let mut cpu = cpu::init_cpu();
+        cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+        let mut mmu = Mmu::new(build_cartridge(vec![opcode]));
+
+        assert_eq!(StepResult::IllegalOpcode { pc: 0x0100, opcode }, cpu.step(&mut mmu));
+        assert!(!cpu.is_locked());
+        assert_eq!(0, cpu.cycles());
+    }
+
+    let mut cpu = cpu::init_cpu();
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+    let mut mmu = Mmu::new(build_cartridge(program()));
+
+    cpu.run(&mut mmu);
+    assert_eq!(0x0102, cpu.regs.pc);
+
+    // The program can go on once PC is moved past the opcode
+    cpu.regs.pc += 1;
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert_eq!(0x43, cpu.regs[A]);
+}
+
+#[test]
+fn test_trap() {
+    let mut cpu = cpu::init_cpu();
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Trap);
+    let mut mmu = Mmu::new(build_cartridge(program()));
+
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert_eq!(StepResult::Breakpoint, cpu.step(&mut mmu));
+    assert_eq!(StepResult::Breakpoint, cpu.step(&mut mmu));
+    assert_eq!(0x0102, cpu.regs.pc);
+    assert!(!cpu.is_locked());
+}
+
+#[test]
+fn test_debugger() {
+    let mut cpu = cpu::init_cpu();
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+    let mut mmu = Mmu::new(build_cartridge(program()));
+
+    let mut debugger = Debugger::new();
+    assert_eq!(StopReason::IllegalOpcode { pc: 0x0102, opcode: 0xD3 }, debugger.resume(&mut cpu, &mut mmu, None));
+}
+
+#[test]
+fn test_policy_from_str() {
+    assert_eq!(Ok(IllegalOpcodePolicy::Lock), "lock".parse());
+    assert_eq!(Ok(IllegalOpcodePolicy::Error), "error".parse());
+    assert_eq!(Ok(IllegalOpcodePolicy::Trap), "trap".parse());
+    assert!("ignore".parse::<IllegalOpcodePolicy>().is_err());
+}
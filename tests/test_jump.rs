@@ -1,11 +1,9 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::{RegisterId::{B, D}};
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -13,21 +11,20 @@ mod common;
 fn test_JP_nn() {
     let cartridge = build_cartridge(vec![
         0xC3, 0x00, 0x20, // JP $2000
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0x2000] = 0x16; // LD D, $42
     mmu[0x2001] = 0x42;
-    mmu[0x2002] = 0x10; // STOP
-    mmu[0x2003] = 0x00;
+    mmu[0x2002] = EXIT;
 
     cpu.run(&mut mmu);
 
     assert_eq!(0x42, cpu.regs[D]);
-    assert_eq!(0x2003, cpu.regs.pc);
+    assert_eq!(0x2002, cpu.regs.pc);
 }
 
 macro_rules! jp_tests {
@@ -39,17 +36,16 @@ macro_rules! jp_tests {
 
             let cartridge = build_cartridge(vec![
                 opcode, 0x00, 0x20, // JP cc,$2000
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0x2000] = 0x16; // LD D, $42
             mmu[0x2001] = 0x42;
-            mmu[0x2002] = 0x10; // STOP
-            mmu[0x2003] = 0x00;
-
+            mmu[0x2002] = EXIT;
+        
             cpu.regs.flags.z = z;
             cpu.regs.flags.c = c;
             cpu.regs[D] = 0x00;
@@ -64,14 +60,14 @@ macro_rules! jp_tests {
 }
 
 jp_tests! {
-    test_JP_NZ_nn_ok: (0xC2, false, false, 0x42, 0x2003),
-    test_JP_NZ_nn_ko: (0xC2, true, false, 0x00, 0x0104),
-    test_JP_Z_nn_ok: (0xCA, true, false, 0x42, 0x2003),
-    test_JP_Z_nn_ko: (0xCA, false, false, 0x00, 0x0104),
-    test_JP_NC_nn_ok: (0xD2, false, false, 0x42, 0x2003),
-    test_JP_NC_nn_ko: (0xD2, false, true, 0x00, 0x0104),
-    test_JP_C_nn_ok: (0xDA, false, true, 0x42, 0x2003),
-    test_JP_C_nn_ko: (0xDA, false, false, 0x00, 0x0104),
+    test_JP_NZ_nn_ok: (0xC2, false, false, 0x42, 0x2002),
+    test_JP_NZ_nn_ko: (0xC2, true, false, 0x00, 0x0103),
+    test_JP_Z_nn_ok: (0xCA, true, false, 0x42, 0x2002),
+    test_JP_Z_nn_ko: (0xCA, false, false, 0x00, 0x0103),
+    test_JP_NC_nn_ok: (0xD2, false, false, 0x42, 0x2002),
+    test_JP_NC_nn_ko: (0xD2, false, true, 0x00, 0x0103),
+    test_JP_C_nn_ok: (0xDA, false, true, 0x42, 0x2002),
+    test_JP_C_nn_ko: (0xDA, false, false, 0x00, 0x0103),
 }
 
 #[test]
@@ -79,21 +75,20 @@ fn test_JP_HL() {
     let cartridge = build_cartridge(vec![
         0x21, 0x00, 0x20, // LD HL, $2000
         0xE9, // JP (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0x2000] = 0x16; // LD D, $42
     mmu[0x2001] = 0x42;
-    mmu[0x2002] = 0x10; // STOP
-    mmu[0x2003] = 0x00;
+    mmu[0x2002] = EXIT;
 
     cpu.run(&mut mmu);
 
     assert_eq!(0x42, cpu.regs[D]);
-    assert_eq!(0x2003, cpu.regs.pc);
+    assert_eq!(0x2002, cpu.regs.pc);
 }
 
 #[test]
@@ -102,10 +97,10 @@ fn test_JR() {
         0x18, 0x02, // JR $032
         0x06, 0x51, // LD B, $51
         0x16, 0x42, // LD D, $42
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.regs[B] = 0x00;
@@ -128,10 +123,10 @@ macro_rules! jr_tests {
                 opcode, 0x02, // JR cc, $032
                 0x06, 0x51, // LD B, $51
                 0x16, 0x42, // LD D, $42
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.regs.flags.z = z;
@@ -0,0 +1,200 @@
+use ruboy::lcd::{render, Frame};
+use ruboy::memory::Mmu;
+
+use crate::common::build_cartridge;
+
+mod common;
+
+/// An MMU with the LCD on, showing the background from map 0x9800 with tiles at 0x8000.
+fn init_mmu() -> Mmu {
+    let mut mmu = Mmu::new(build_cartridge(vec![]));
+    mmu[0xFF40] = 0x91;
+    mmu[0xFF47] = 0xE4;
+    mmu[0xFF48] = 0xE4;
+    mmu[0xFF49] = 0x1B;
+    mmu
+}
+
+/// Fills tile `index` at 0x8000 with a single color.
+fn fill_tile(mmu: &mut Mmu, index: u16, color: u8) {
+    for row in 0..8 {
+        mmu[0x8000 + index * 16 + row * 2] = if color & 1 != 0 { 0xFF } else { 0x00 };
+        mmu[0x8000 + index * 16 + row * 2 + 1] = if color & 2 != 0 { 0xFF } else { 0x00 };
+    }
+}
+
+#[test]
+fn test_lcd_off() {
+    let mut mmu = init_mmu();
+    fill_tile(&mut mmu, 0, 3);
+    mmu[0xFF40] = 0x11;
+
+    assert_eq!(Frame::blank(), render(&mmu));
+}
+
+#[test]
+fn test_background() {
+    let mut mmu = init_mmu();
+    fill_tile(&mut mmu, 1, 3);
+    // Left half of the tile in color 1, right half in color 2
+    mmu[0x8020] = 0xF0;
+    mmu[0x8021] = 0x0F;
+    mmu[0x9800 + 32 + 1] = 1;
+    mmu[0x9800 + 2] = 2;
+
+    let frame = render(&mmu);
+    assert_eq!(0, frame.pixel(0, 0));
+    assert_eq!(3, frame.pixel(8, 8));
+    assert_eq!(3, frame.pixel(15, 15));
+    assert_eq!(0, frame.pixel(16, 8));
+    assert_eq!(1, frame.pixel(16, 0));
+    assert_eq!(2, frame.pixel(20, 0));
+    assert_eq!(0, frame.pixel(16, 1));
+
+    // BGP maps colors to shades
+    mmu[0xFF47] = 0x1B;
+    assert_eq!(0, render(&mmu).pixel(8, 8));
+}
+
+#[test]
+fn test_signed_tile_data() {
+    let mut mmu = init_mmu();
+    mmu[0xFF40] = 0x81;
+    // Tile 0xFF is stored at 0x8FF0 when using 0x9000 as base
+    for address in 0x8FF0..0x9000 {
+        mmu[address] = 0xFF;
+    }
+    mmu[0x9800] = 0xFF;
+
+    assert_eq!(3, render(&mmu).pixel(0, 0));
+    assert_eq!(0, render(&mmu).pixel(8, 0));
+}
+
+#[test]
+fn test_scroll() {
+    let mut mmu = init_mmu();
+    fill_tile(&mut mmu, 1, 3);
+    mmu[0x9800] = 1;
+    mmu[0xFF42] = 4;
+    mmu[0xFF43] = 6;
+
+    let frame = render(&mmu);
+    assert_eq!(3, frame.pixel(0, 0));
+    assert_eq!(3, frame.pixel(1, 3));
+    assert_eq!(0, frame.pixel(2, 3));
+    assert_eq!(0, frame.pixel(1, 4));
+
+    // The background wraps around
+    mmu[0xFF42] = 0;
+    mmu[0xFF43] = 0xFC;
+    assert_eq!(0, render(&mmu).pixel(3, 0));
+    assert_eq!(3, render(&mmu).pixel(4, 0));
+    mmu[0x9800 + 31] = 1;
+    assert_eq!(3, render(&mmu).pixel(3, 0));
+}
+
+#[test]
+fn test_window() {
+    let mut mmu = init_mmu();
+    fill_tile(&mut mmu, 1, 3);
+    mmu[0xFF40] = 0xF1;
+    mmu[0x9C00] = 1;
+    mmu[0xFF4A] = 10;
+    mmu[0xFF4B] = 27;
+
+    let frame = render(&mmu);
+    assert_eq!(0, frame.pixel(20, 9));
+    assert_eq!(0, frame.pixel(19, 10));
+    assert_eq!(3, frame.pixel(20, 10));
+    assert_eq!(3, frame.pixel(27, 17));
+    assert_eq!(0, frame.pixel(28, 10));
+}
+
+#[test]
+fn test_sprites() {
+    let mut mmu = init_mmu();
+    mmu[0xFF40] = 0x93;
+    fill_tile(&mut mmu, 1, 1);
+    fill_tile(&mut mmu, 2, 3);
+
+    // Sprite at (0, 0) using OBP0
+    mmu[0xFE00] = 16;
+    mmu[0xFE01] = 8;
+    mmu[0xFE02] = 1;
+    // Sprite overlapping the first one, drawn below it, using OBP1
+    mmu[0xFE04] = 20;
+    mmu[0xFE05] = 12;
+    mmu[0xFE06] = 1;
+    mmu[0xFE07] = 0x10;
+
+    let frame = render(&mmu);
+    assert_eq!(1, frame.pixel(0, 0));
+    assert_eq!(1, frame.pixel(7, 7));
+    assert_eq!(2, frame.pixel(8, 8));
+    assert_eq!(0, frame.pixel(8, 0));
+
+    // Sprites are hidden when disabled
+    mmu[0xFF40] = 0x91;
+    assert_eq!(0, render(&mmu).pixel(0, 0));
+}
+
+#[test]
+fn test_sprite_priority() {
+    let mut mmu = init_mmu();
+    mmu[0xFF40] = 0x93;
+    fill_tile(&mut mmu, 1, 1);
+    fill_tile(&mut mmu, 2, 3);
+
+    // The sprite with the lowest X is drawn on top, regardless of its OAM position
+    mmu[0xFE00] = 16;
+    mmu[0xFE01] = 12;
+    mmu[0xFE02] = 1;
+    mmu[0xFE04] = 16;
+    mmu[0xFE05] = 8;
+    mmu[0xFE06] = 2;
+
+    assert_eq!(3, render(&mmu).pixel(4, 0));
+
+    // A sprite behind the background is only visible over color 0
+    mmu[0xFE07] = 0x80;
+    mmu[0x9800] = 1;
+    let frame = render(&mmu);
+    assert_eq!(1, frame.pixel(0, 0));
+    assert_eq!(1, frame.pixel(8, 0));
+}
+
+#[test]
+fn test_sprite_flip() {
+    let mut mmu = init_mmu();
+    mmu[0xFF40] = 0x93;
+    // Only the top left pixel is set
+    mmu[0x8010] = 0x80;
+    mmu[0x8011] = 0x80;
+
+    mmu[0xFE00] = 16;
+    mmu[0xFE01] = 8;
+    mmu[0xFE02] = 1;
+    assert_eq!(3, render(&mmu).pixel(0, 0));
+
+    mmu[0xFE03] = 0x60;
+    let frame = render(&mmu);
+    assert_eq!(0, frame.pixel(0, 0));
+    assert_eq!(3, frame.pixel(7, 7));
+}
+
+#[test]
+fn test_sprites_per_line() {
+    let mut mmu = init_mmu();
+    mmu[0xFF40] = 0x93;
+    fill_tile(&mut mmu, 1, 3);
+
+    for i in 0..11 {
+        mmu[0xFE00 + i * 4] = 16;
+        mmu[0xFE00 + i * 4 + 1] = 8 + i as u8 * 8;
+        mmu[0xFE00 + i * 4 + 2] = 1;
+    }
+
+    let frame = render(&mmu);
+    assert_eq!(3, frame.pixel(79, 0));
+    assert_eq!(0, frame.pixel(80, 0));
+}
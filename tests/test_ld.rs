@@ -1,12 +1,10 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::{Register16Id::{BC, DE, HL}, RegisterId::{A, B, C, D, E, H, L}};
 use ruboy::opcodes::Operand::{Byte, DirectAddress, IndirectAddress, Register};
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -19,10 +17,10 @@ macro_rules! ld_nn_n_tests {
 
             let cartridge = build_cartridge(vec![
                 opcode, 0x69, // LD x, $69
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.run(&mut mmu);
@@ -54,32 +52,31 @@ macro_rules! ld_r1_r2_tests {
 
             let cartridge = build_cartridge(vec![
                 0x3E, 0x0A, // LD A, $0A
-                0x06, 0x0B, // LD B, $0B
+                0x06, 0xCB, // LD B, $CB
                 0x0E, 0x0C, // LD C, $0C
-                0x16, 0x0D, // LD D, $0D
+                0x16, 0xCD, // LD D, $CD
                 0x1E, 0x0E, // LD E, $0E
                 0x26, 0xFF, // LD H, $FF
                 0x2E, 0x11, // LD L, $11
                 opcode, // LD r1, r2
-                0x10, 0x00, // STOP or address $0010 for certain opcodes
-                0x10, 0x00, // STOP
+                0x18, 0x00, // JR +0, or operand $0018 for certain opcodes
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0xFF11] = 0x22;
-            mmu[0x0B0C] = 0x33;
-            mmu[0x0D0E] = 0x44;
-            mmu[0x0010] = 0x55;
+            mmu[0xCB0C] = 0x33;
+            mmu[0xCD0E] = 0x44;
+            mmu[0x0018] = 0x55;
 
             cpu.run(&mut mmu);
 
             let val1 = match r1 {
                 IndirectAddress(HL) => mmu[0xFF11],
-                IndirectAddress(BC) => mmu[0x0B0C],
-                IndirectAddress(DE) => mmu[0x0D0E],
-                DirectAddress => mmu[0x0010],
+                IndirectAddress(BC) => mmu[0xCB0C],
+                IndirectAddress(DE) => mmu[0xCD0E],
                 Register(reg) => cpu.regs[reg],
                 _ => panic!(),
             };
@@ -89,7 +86,7 @@ macro_rules! ld_r1_r2_tests {
                 IndirectAddress(BC) => 0x33,
                 IndirectAddress(DE) => 0x44,
                 DirectAddress => 0x55,
-                Byte => 0x10,
+                Byte => 0x18,
                 Register(reg) => cpu.regs[reg],
                 _ => panic!(),
             };
@@ -179,17 +176,32 @@ ld_r1_r2_tests! {
 
     test_LD_BCA: (0x02, IndirectAddress(BC), Register(A)),
     test_LD_DEA: (0x12, IndirectAddress(DE), Register(A)),
-    test_LD_nnA: (0xEA, DirectAddress, Register(A)),
+}
+
+#[test]
+fn test_LD_nnA() {
+    let cartridge = build_cartridge(vec![
+        0x3E, 0x0A, // LD A, $0A
+        0xEA, 0x18, 0xC0, // LD ($C018), A
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    cpu.run(&mut mmu);
+
+    assert_eq!(0x0A, mmu[0xC018]);
 }
 
 #[test]
 fn test_LD_BCnn() {
     let cartridge = build_cartridge(vec![
         0x01, 0xAA, 0xBB, // LD BC, 0xBBAA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -202,10 +214,10 @@ fn test_LD_BCnn() {
 fn test_LD_DEnn() {
     let cartridge = build_cartridge(vec![
         0x11, 0xBB, 0xCC, // LD DE, 0xCCBB
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -218,10 +230,10 @@ fn test_LD_DEnn() {
 fn test_LD_HLnn() {
     let cartridge = build_cartridge(vec![
         0x21, 0xCC, 0xDD, // LD HL, 0xDDCC
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -234,10 +246,10 @@ fn test_LD_HLnn() {
 fn test_LD_SPnn() {
     let cartridge = build_cartridge(vec![
         0x31, 0xDD, 0xEE, // LD SP, 0xEEDD
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -250,10 +262,10 @@ fn test_LD_SPHL() {
     let cartridge = build_cartridge(vec![
         0x21, 0x12, 0x34, // LD HL, 0x3412
         0xF9, // LD SP, HL
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -265,10 +277,10 @@ fn test_LD_SPHL() {
 fn test_LD_nnSP() {
     let cartridge = build_cartridge(vec![
         0x08, 0xDD, 0xEE, // LD 0xEEDD, SP
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -281,10 +293,10 @@ fn test_LD_A_C_() {
     let cartridge = build_cartridge(vec![
         0x0E, 0x11, // LD C, $11
         0xF2, // LD A, ($FF00 + C)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xFF00 + 0x11] = 0x21;
@@ -300,10 +312,10 @@ fn test_LD_C_A_() {
         0x3E, 0x22, // LD A, $22
         0x0E, 0x11, // LD C, $11
         0xE2, // LD ($FF00 + C), A
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -316,10 +328,10 @@ fn test_LDD_AHL() {
     let cartridge = build_cartridge(vec![
         0x21, 0x11, 0xFF, // LD HL, $FF11
         0x3A, // LDD A, (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xFF11] = 0x66;
@@ -336,10 +348,10 @@ fn test_LDD_HLA() {
         0x21, 0x11, 0xFF, // LD HL, $FF11
         0x3E, 0x66, // LD A, $66
         0x32, // LDD (HL), A
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -353,10 +365,10 @@ fn test_LDI_AHL() {
     let cartridge = build_cartridge(vec![
         0x21, 0x11, 0xFF, // LD HL, $FF11
         0x2A, // LDI A, (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xFF11] = 0x66;
@@ -373,10 +385,10 @@ fn test_LDI_HLA() {
         0x21, 0x11, 0xFF, // LD HL, $FF11
         0x3E, 0x66, // LD A, $66
         0x22, // LDI (HL), A
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -390,10 +402,10 @@ fn test_LDH_nA() {
     let cartridge = build_cartridge(vec![
         0x3E, 0x66, // LD A, $66
         0xE0, 0x01, // LD ($FF00 + $01), A
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -405,10 +417,10 @@ fn test_LDH_nA() {
 fn test_LDH_An() {
     let cartridge = build_cartridge(vec![
         0xF0, 0x01, // LD A, ($FF00 + $01)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xFF01] = 0x77;
@@ -422,10 +434,10 @@ fn test_LDH_An() {
 fn test_LDHL_SPn() {
     let cartridge = build_cartridge(vec![
         0xF8, 0x02, // LDHL SP, $02
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ruboy::memory::{AccessKind, MemoryAccess, Mmu};
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+fn record(mmu: &mut Mmu, kind: AccessKind, range: std::ops::RangeInclusive<u16>) -> Rc<RefCell<Vec<MemoryAccess>>> {
+    let accesses = Rc::new(RefCell::new(Vec::new()));
+    let sink = accesses.clone();
+    mmu.add_hook(kind, range, move |access| sink.borrow_mut().push(*access));
+    accesses
+}
+
+#[test]
+fn test_read_write_hooks() {
+    let cartridge = build_cartridge(vec![
+        0x21, 0x00, 0xC0, // LD HL, $C000
+        0x36, 0x42, // LD (HL), $42
+        0x34, // INC (HL)
+        0xFA, 0x00, 0xC0, // LD A, ($C000)
+        0xEA, 0x10, 0xC0, // LD ($C010), A
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    let reads = record(&mut mmu, AccessKind::Read, 0xC000..=0xC0FF);
+    let writes = record(&mut mmu, AccessKind::Write, 0xC000..=0xC0FF);
+
+    cpu.run(&mut mmu);
+
+    assert_eq!(vec![
+        MemoryAccess { kind: AccessKind::Read, address: 0xC000, value: 0x42, pc: 0x0105 },
+        MemoryAccess { kind: AccessKind::Read, address: 0xC000, value: 0x43, pc: 0x0106 },
+    ], *reads.borrow());
+
+    assert_eq!(vec![
+        MemoryAccess { kind: AccessKind::Write, address: 0xC000, value: 0x42, pc: 0x0103 },
+        MemoryAccess { kind: AccessKind::Write, address: 0xC000, value: 0x43, pc: 0x0105 },
+        MemoryAccess { kind: AccessKind::Write, address: 0xC010, value: 0x43, pc: 0x0109 },
+    ], *writes.borrow());
+}
+
+#[test]
+fn test_execute_hooks() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        0xCB, 0x37, // SWAP A
+        0x3E, 0x01, // LD A, $01
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    let executed = record(&mut mmu, AccessKind::Execute, 0x0000..=0xFFFF);
+
+    cpu.run(&mut mmu);
+
+    let addresses: Vec<u16> = executed.borrow().iter().map(|access| access.address).collect();
+    assert_eq!(vec![0x0100, 0x0101, 0x0102, 0x0103], addresses);
+}
+
+#[test]
+fn test_stack_hooks() {
+    let cartridge = build_cartridge(vec![
+        0x01, 0x34, 0x12, // LD BC, $1234
+        0xC5, // PUSH BC
+        0xD1, // POP DE
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    let reads = record(&mut mmu, AccessKind::Read, 0xFF80..=0xFFFE);
+    let writes = record(&mut mmu, AccessKind::Write, 0xFF80..=0xFFFE);
+
+    cpu.run(&mut mmu);
+
+    let written: Vec<(u16, u8)> = writes.borrow().iter().map(|a| (a.address, a.value)).collect();
+    let read: Vec<(u16, u8)> = reads.borrow().iter().map(|a| (a.address, a.value)).collect();
+    assert_eq!(vec![(0xFFFD, 0x12), (0xFFFC, 0x34)], written);
+    assert_eq!(vec![(0xFFFC, 0x34), (0xFFFD, 0x12)], read);
+}
+
+#[test]
+fn test_remove_hook() {
+    let mut mmu = Mmu::new(build_cartridge(vec![]));
+
+    let count = Rc::new(RefCell::new(0));
+    let sink = count.clone();
+    let id = mmu.add_hook(AccessKind::Write, 0xC000..=0xC000, move |_| *sink.borrow_mut() += 1);
+
+    mmu.write(0xC000, 1);
+    mmu.write(0xC001, 1);
+    assert_eq!(1, *count.borrow());
+
+    assert!(mmu.remove_hook(id));
+    assert!(!mmu.remove_hook(id));
+
+    mmu.write(0xC000, 2);
+    assert_eq!(1, *count.borrow());
+    assert_eq!(2, mmu.read(0xC000));
+}
+
+#[test]
+fn test_index_does_not_trigger_hooks() {
+    let mut mmu = Mmu::new(build_cartridge(vec![]));
+
+    let accesses = record(&mut mmu, AccessKind::Write, 0x0000..=0xFFFF);
+    mmu[0xC000] = 0x12;
+
+    assert!(accesses.borrow().is_empty());
+}
@@ -1,11 +1,9 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::{A, B, C, D, E, H, L};
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -25,10 +23,10 @@ macro_rules! swap_tests {
                 0x26, 0xA5, // LD H, $A5
                 0x2E, 0x96, // LD L, $96
                 0xCB, opcode, // SWAP x
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.run(&mut mmu);
@@ -55,10 +53,10 @@ fn test_SWAP_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x36, // SWAP (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x87;
@@ -73,10 +71,10 @@ fn test_CPL() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b10101100, // LD A, $AC
         0x2F, // CPL
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -88,10 +86,10 @@ fn test_CPL() {
 fn test_CCF_false() {
     let cartridge = build_cartridge(vec![
         0x3F, // CCF
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.regs.flags.z = true;
@@ -101,20 +99,20 @@ fn test_CCF_false() {
 
     cpu.run(&mut mmu);
 
-    assert_eq!(true, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.n);
-    assert_eq!(false, cpu.regs.flags.h);
-    assert_eq!(true, cpu.regs.flags.c);
+    assert!(cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.n);
+    assert!(!cpu.regs.flags.h);
+    assert!(cpu.regs.flags.c);
 }
 
 #[test]
 fn test_CCF_true() {
     let cartridge = build_cartridge(vec![
         0x3F, // CCF
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.regs.flags.z = true;
@@ -124,20 +122,20 @@ fn test_CCF_true() {
 
     cpu.run(&mut mmu);
 
-    assert_eq!(true, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.n);
-    assert_eq!(false, cpu.regs.flags.h);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.n);
+    assert!(!cpu.regs.flags.h);
+    assert!(!cpu.regs.flags.c);
 }
 
 #[test]
 fn test_SCF() {
     let cartridge = build_cartridge(vec![
         0x37, // SCF
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.regs.flags.z = true;
@@ -147,10 +145,10 @@ fn test_SCF() {
 
     cpu.run(&mut mmu);
 
-    assert_eq!(true, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.n);
-    assert_eq!(false, cpu.regs.flags.h);
-    assert_eq!(true, cpu.regs.flags.c);
+    assert!(cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.n);
+    assert!(!cpu.regs.flags.h);
+    assert!(cpu.regs.flags.c);
 }
 
 #[test]
@@ -158,10 +156,10 @@ fn test_NOP() {
     let cartridge = build_cartridge(vec![
         0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
         0x3E, 0xAA, // LD A, $AA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
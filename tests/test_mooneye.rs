@@ -0,0 +1,135 @@
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu::{StepResult, CYCLES_PER_FRAME};
+use ruboy::memory::Mmu;
+use ruboy::testrom::{run_mooneye, TestResult};
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+const MOONEYE_DIR: &str = "rom/mooneye";
+
+/// Mooneye ROMs report their result within a few seconds of emulated time
+const MAX_CYCLES: u64 = 600 * CYCLES_PER_FRAME;
+
+fn run_program(program: Vec<u8>) -> TestResult {
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program));
+
+    run_mooneye(&mut cpu, &mut mmu, MAX_CYCLES)
+}
+
+#[test]
+fn test_mooneye_pass() {
+    let program = vec![
+        0x06, 0x03, // LD B, 3
+        0x0E, 0x05, // LD C, 5
+        0x16, 0x08, // LD D, 8
+        0x1E, 0x0D, // LD E, 13
+        0x26, 0x15, // LD H, 21
+        0x2E, 0x22, // LD L, 34
+        0x40, // LD B, B
+        EXIT,
+    ];
+
+    assert_eq!(TestResult::Passed, run_program(program));
+}
+
+#[test]
+fn test_mooneye_fail() {
+    let program = vec![
+        0x3E, 0x42, // LD A, $42
+        0x47, // LD B, A
+        0x4F, // LD C, A
+        0x57, // LD D, A
+        0x5F, // LD E, A
+        0x67, // LD H, A
+        0x6F, // LD L, A
+        0x40, // LD B, B
+        EXIT,
+    ];
+
+    assert_eq!(TestResult::Failed("B=42 C=42 D=42 E=42 H=42 L=42".to_owned()), run_program(program));
+}
+
+#[test]
+fn test_mooneye_unfinished() {
+    assert_eq!(TestResult::Unfinished, run_program(vec![0x00, EXIT]));
+    assert_eq!(TestResult::Unfinished, run_program(vec![0x00, 0x18, 0xFD])); // JR -3
+}
+
+#[test]
+fn test_breakpoint_opcode() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        0x40, // LD B, B
+        0xCB, 0x40, // BIT 0, B
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    // Disabled by default
+    cpu.run(&mut mmu);
+    assert_eq!(0x0104, cpu.regs.pc);
+
+    let mut cpu = common::init_cpu();
+    cpu.set_breakpoint_opcode(Some(0x40));
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert_eq!(StepResult::Breakpoint, cpu.step(&mut mmu));
+    assert_eq!(0x0102, cpu.regs.pc);
+    // Prefixed opcodes are not breakpoints
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert_eq!(StepResult::Stopped, cpu.step(&mut mmu));
+}
+
+fn find_roms(dir: &Path, roms: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("Couldn't list ROMs") {
+        let path = entry.expect("Couldn't list ROMs").path();
+
+        if path.is_dir() {
+            find_roms(&path, roms);
+        } else if path.extension().is_some_and(|ext| ext == "gb") {
+            roms.push(path);
+        }
+    }
+}
+
+/// Runs every ROM of the Mooneye acceptance suite found in `rom/mooneye`. The suite is not
+/// distributed with the repository, so the test is ignored by default.
+#[test]
+#[ignore = "needs the Mooneye acceptance ROMs in rom/mooneye"]
+fn test_mooneye_acceptance() {
+    let dir = Path::new(MOONEYE_DIR).join("acceptance");
+    assert!(dir.is_dir(), "{} not found", dir.display());
+
+    let mut roms = Vec::new();
+    find_roms(&dir, &mut roms);
+    roms.sort();
+
+    let mut failures = Vec::new();
+    for rom in &roms {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut cpu = common::init_cpu();
+            let mut mmu = Mmu::new(Cartridge::new(&rom.to_string_lossy()));
+            run_mooneye(&mut cpu, &mut mmu, MAX_CYCLES)
+        }));
+
+        let description = match &result {
+            Ok(result) => format!("{:?}", result),
+            Err(_) => "emulator error".to_owned(),
+        };
+        println!("{}  {}", description, rom.display());
+
+        if result.ok() != Some(TestResult::Passed) {
+            failures.push(format!("{}: {}", rom.display(), description));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} Mooneye ROMs failed:\n{}\n", failures.len(), roms.len(), failures.join("\n"));
+}
@@ -0,0 +1,209 @@
+use ruboy::opcodes::{decode, disassemble, Instruction};
+
+const UNUSED_OPCODES: [u8; 11] = [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD];
+
+#[rustfmt::skip]
+const LENGTHS: [u8; 256] = [
+    1, 3, 1, 1, 1, 1, 2, 1, 3, 1, 1, 1, 1, 1, 2, 1, // 0x
+    2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1, // 1x
+    2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1, // 2x
+    2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1, // 3x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 4x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 5x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 6x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 7x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 8x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 9x
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // Ax
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // Bx
+    1, 1, 3, 3, 3, 1, 2, 1, 1, 1, 3, 2, 3, 3, 2, 1, // Cx
+    1, 1, 3, 0, 3, 1, 2, 1, 1, 1, 3, 0, 3, 0, 2, 1, // Dx
+    2, 1, 1, 0, 0, 1, 2, 1, 2, 1, 3, 0, 0, 0, 2, 1, // Ex
+    2, 1, 1, 1, 0, 1, 2, 1, 2, 1, 3, 1, 0, 0, 2, 1, // Fx
+];
+
+/// Cycles when the branch is not taken, 0 for unused opcodes and the 0xCB prefix.
+#[rustfmt::skip]
+const CYCLES: [u8; 256] = [
+     4, 12,  8,  8,  4,  4,  8,  4, 20,  8,  8,  8,  4,  4,  8,  4, // 0x
+     4, 12,  8,  8,  4,  4,  8,  4, 12,  8,  8,  8,  4,  4,  8,  4, // 1x
+     8, 12,  8,  8,  4,  4,  8,  4,  8,  8,  8,  8,  4,  4,  8,  4, // 2x
+     8, 12,  8,  8, 12, 12, 12,  4,  8,  8,  8,  8,  4,  4,  8,  4, // 3x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 4x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 5x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 6x
+     8,  8,  8,  8,  8,  8,  4,  8,  4,  4,  4,  4,  4,  4,  8,  4, // 7x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 8x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // 9x
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // Ax
+     4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4, // Bx
+     8, 12, 12, 16, 12, 16,  8, 16,  8, 16, 12,  0, 12, 24,  8, 16, // Cx
+     8, 12, 12,  0, 12, 16,  8, 16,  8, 16, 12,  0, 12,  0,  8, 16, // Dx
+    12, 12,  8,  0,  0, 16,  8, 16, 16,  4, 16,  0,  0,  0,  8, 16, // Ex
+    12, 12,  8,  4,  0, 16,  8, 16, 12,  8, 16,  4,  0,  0,  8, 16, // Fx
+];
+
+#[test]
+fn test_instruction_cycles() {
+    for opcode in 0..=0xFFu8 {
+        if UNUSED_OPCODES.contains(&opcode) || opcode == 0xCB {
+            continue;
+        }
+
+        let instr = Instruction::try_from((opcode, 0x00)).unwrap();
+
+        assert_eq!(CYCLES[opcode as usize], instr.cycles, "Wrong cycles for {:#04x} {}", opcode, instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_prefixed_instruction_cycles() {
+    for opcode in 0..=0xFFu8 {
+        let instr = Instruction::try_from((0xCB, opcode)).unwrap();
+        let expected = match opcode {
+            0x46 | 0x4E | 0x56 | 0x5E | 0x66 | 0x6E | 0x76 | 0x7E => 12, // BIT n,(HL)
+            _ if opcode & 0x07 == 0x06 => 16,
+            _ => 8,
+        };
+
+        assert_eq!(expected, instr.cycles, "Wrong cycles for 0xcb {:#04x} {}", opcode, instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_instruction_lengths() {
+    for opcode in 0..=0xFFu8 {
+        if UNUSED_OPCODES.contains(&opcode) {
+            continue;
+        }
+
+        let instr = Instruction::try_from((opcode, 0x00)).unwrap();
+
+        assert_eq!(LENGTHS[opcode as usize], instr.length(), "Wrong length for {:#04x} {}", opcode, instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_prefixed_instruction_lengths() {
+    for opcode in 0..=0xFFu8 {
+        let instr = Instruction::try_from((0xCB, opcode)).unwrap();
+
+        assert!(instr.is_prefixed());
+        assert_eq!(2, instr.opcode_size());
+        assert_eq!(0, instr.immediate_size());
+        assert_eq!(2, instr.length(), "Wrong length for 0xcb {:#04x} {}", opcode, instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_unused_opcodes() {
+    for opcode in UNUSED_OPCODES {
+        assert!(Instruction::try_from((opcode, 0x00)).is_err());
+    }
+}
+
+#[test]
+fn test_immediate_sizes() {
+    let sizes = [
+        (0x00, 0), // NOP
+        (0x01, 2), // LD BC,d16
+        (0x06, 1), // LD B,d8
+        (0x08, 2), // LD (a16),SP
+        (0x10, 1), // STOP
+        (0x18, 1), // JR r8
+        (0xC3, 2), // JP a16
+        (0xCD, 2), // CALL a16
+        (0xE0, 1), // LDH (a8),A
+        (0xE2, 0), // LD (C),A
+        (0xE8, 1), // ADD SP,r8
+        (0xEA, 2), // LD (a16),A
+        (0xF8, 1), // LD HL,SP+r8
+    ];
+
+    for (opcode, size) in sizes {
+        let instr = Instruction::try_from((opcode, 0x00)).unwrap();
+
+        assert_eq!(size, instr.immediate_size(), "Wrong immediate size for {}", instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_conditional_instructions() {
+    let conditional = [
+        0x20, 0x28, 0x30, 0x38, // JR cc
+        0xC2, 0xCA, 0xD2, 0xDA, // JP cc
+        0xC4, 0xCC, 0xD4, 0xDC, // CALL cc
+        0xC0, 0xC8, 0xD0, 0xD8, // RET cc
+    ];
+
+    for opcode in 0..=0xFFu8 {
+        if UNUSED_OPCODES.contains(&opcode) || opcode == 0xCB {
+            continue;
+        }
+
+        let instr = Instruction::try_from((opcode, 0x00)).unwrap();
+
+        assert_eq!(conditional.contains(&opcode), instr.is_conditional(), "{}", instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_branch_instructions() {
+    let branches = [
+        0x18, 0x20, 0x28, 0x30, 0x38, // JR
+        0xC2, 0xC3, 0xCA, 0xD2, 0xDA, 0xE9, // JP
+        0xC4, 0xCC, 0xCD, 0xD4, 0xDC, // CALL
+        0xC0, 0xC8, 0xC9, 0xD0, 0xD8, 0xD9, // RET, RETI
+        0xC7, 0xCF, 0xD7, 0xDF, 0xE7, 0xEF, 0xF7, 0xFF, // RST
+    ];
+
+    for opcode in 0..=0xFFu8 {
+        if UNUSED_OPCODES.contains(&opcode) || opcode == 0xCB {
+            continue;
+        }
+
+        let instr = Instruction::try_from((opcode, 0x00)).unwrap();
+
+        assert_eq!(branches.contains(&opcode), instr.is_branch(), "{}", instr.mnemonic);
+    }
+}
+
+#[test]
+fn test_disassemble() {
+    let programs: [(&[u8], &str); 10] = [
+        (&[0x00], "NOP"),
+        (&[0x3E, 0xF0], "LD A,$F0"),
+        (&[0x21, 0x34, 0x12], "LD HL,$1234"),
+        (&[0xC3, 0x50, 0x01], "JP $0150"),
+        (&[0x18, 0xFE], "JR -2"),
+        (&[0x20, 0x05], "JR NZ,+5"),
+        (&[0xE0, 0x44], "LDH ($44),A"),
+        (&[0xF8, 0xFF], "LD HL,SP-1"),
+        (&[0x10, 0x00], "STOP"),
+        (&[0xCB, 0x7C], "BIT 7,H"),
+    ];
+
+    for (bytes, expected) in programs {
+        assert_eq!(Some(expected.to_owned()), disassemble(bytes));
+    }
+}
+
+#[test]
+fn test_disassemble_invalid() {
+    assert_eq!(None, disassemble(&[]));
+    assert_eq!(None, disassemble(&[0xD3]));
+    assert_eq!(None, disassemble(&[0xC3, 0x50]));
+}
+
+#[test]
+fn test_decode_matches_instructions() {
+    for opcode in 0..=0xFFu8 {
+        let decoded = decode(opcode, 0x00).map(|instr| instr.mnemonic);
+        let expected = Instruction::try_from((opcode, 0x00)).ok().map(|instr| instr.mnemonic);
+        assert_eq!(expected, decoded, "Opcode {:#04x}", opcode);
+
+        let decoded = decode(0xCB, opcode).map(|instr| instr.mnemonic);
+        let expected = Instruction::try_from((0xCB, opcode)).ok().map(|instr| instr.mnemonic);
+        assert_eq!(expected, decoded, "Opcode 0xcb {:#04x}", opcode);
+    }
+}
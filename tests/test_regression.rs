@@ -0,0 +1,75 @@
+use std::path::Path;
+use std::{env, fs};
+
+use ruboy::lcd::Frame;
+use ruboy::regression::{check_dir, Outcome};
+use ruboy::screenshot::{encode_png, Palette};
+
+use crate::common::build_cartridge;
+
+mod common;
+
+/// Long enough for the slowest blargg ROM to finish
+const FRAMES: u64 = 1200;
+
+/// ROMs which need interrupts to be dispatched, and have no golden image yet
+const KNOWN_FAILURES: &[&str] = &["02-interrupts.gb", "cpu_instrs.gb"];
+
+/// Set `RUBOY_UPDATE_GOLDENS=1` to rewrite the golden images from the current output.
+#[test]
+fn test_blargg_screenshots() {
+    let update = env::var("RUBOY_UPDATE_GOLDENS").is_ok_and(|value| value == "1");
+    let results = check_dir(Path::new("rom/blargg"), Path::new("tests/goldens/blargg"), FRAMES, update)
+        .expect("Couldn't run the ROMs");
+
+    for result in &results {
+        println!("{}", result);
+    }
+
+    let (known, failures): (Vec<_>, Vec<_>) = results.iter()
+        .filter(|result| !result.is_ok())
+        .partition(|result| KNOWN_FAILURES.iter().any(|rom| result.rom.ends_with(rom)));
+
+    for result in known {
+        println!("Known failure, not checked: {}", result);
+    }
+
+    let failures: Vec<String> = failures.into_iter()
+        .map(|result| result.to_string())
+        .collect();
+    assert!(failures.is_empty(), "Screenshot regressions:\n{}\n", failures.join("\n"));
+}
+
+#[test]
+fn test_check_dir_outcomes() {
+    let dir = env::temp_dir().join(format!("ruboy-regression-{}", std::process::id()));
+    let (roms, goldens) = (dir.join("roms"), dir.join("goldens"));
+    fs::create_dir_all(&roms).unwrap();
+
+    // Draws tile 1 in the top left corner, then stops
+    let program = vec![
+        0x3E, 0xFF, // LD A, $FF
+        0xEA, 0x10, 0x80, // LD ($8010), A
+        0x3E, 0x01, // LD A, $01
+        0xEA, 0x00, 0x98, // LD ($9800), A
+        0x18, 0xFE, // JR -2
+    ];
+    fs::write(roms.join("tile.gb"), build_cartridge(program).content).unwrap();
+    fs::write(roms.join("readme.txt"), "not a ROM").unwrap();
+    // Runs into an unsupported opcode
+    fs::write(roms.join("invalid.gb"), build_cartridge(vec![0xD3]).content).unwrap();
+
+    let outcomes = |update| -> Vec<Outcome> {
+        check_dir(&roms, &goldens, 10, update).unwrap().into_iter().map(|result| result.outcome).collect()
+    };
+
+    assert!(matches!(outcomes(false)[..], [Outcome::Error(_), Outcome::MissingGolden]));
+    assert!(matches!(outcomes(true)[..], [Outcome::Error(_), Outcome::Updated]));
+    assert!(matches!(outcomes(false)[..], [Outcome::Error(_), Outcome::Passed]));
+
+    fs::write(goldens.join("tile.png"), encode_png(&Frame::blank(), Palette::Grayscale)).unwrap();
+    assert!(matches!(outcomes(false)[..], [Outcome::Error(_), Outcome::Mismatch]));
+    assert!(goldens.join("tile.actual.png").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
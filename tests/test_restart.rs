@@ -1,11 +1,9 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::B;
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -21,17 +19,17 @@ macro_rules! rst_tests {
             ]);
 
             cartridge.content[0x00000..0x040].copy_from_slice(vec![
-                0x06, 0x01, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $01
-                0x06, 0x02, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $02
-                0x06, 0x03, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $03
-                0x06, 0x04, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $04
-                0x06, 0x05, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $05
-                0x06, 0x06, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $06
-                0x06, 0x07, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $07
-                0x06, 0x08, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $08
+                0x06, 0x01, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $01
+                0x06, 0x02, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $02
+                0x06, 0x03, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $03
+                0x06, 0x04, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $04
+                0x06, 0x05, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $05
+                0x06, 0x06, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $06
+                0x06, 0x07, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $07
+                0x06, 0x08, EXIT, 0x00, 0x00, 0x00, 0x00, 0x00, // LD B, $08
             ].as_slice());
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.run(&mut mmu);
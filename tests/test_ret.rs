@@ -1,11 +1,9 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::D;
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -17,10 +15,10 @@ fn test_RET() {
         0xC9, // RET
         0x18, 0xF2, // JR -2 (infinite loop)
         0x16, 0x42, // // LD D, $42
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -36,10 +34,10 @@ fn test_RETI() {
         0xD9, // RETI
         0x18, 0xF2, // JR -2 (infinite loop)
         0x16, 0x42, // // LD D, $42
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -56,16 +54,16 @@ macro_rules! ret_tests {
             let (opcode, z, c, expected_d) = $value;
 
             let cartridge = build_cartridge(vec![
-                0x21, 0x09, 0x01, // LD HL, $0109
+                0x21, 0x08, 0x01, // LD HL, $0108
                 0xE5, // PUSH HL
                 opcode, // RET cc
                 0x16, 0x69, // LD D, $69
-                0x10, 0x00, // STOP
+                EXIT,
                 0x16, 0x42, // LD D, $42
-                0x10, 0x00 // STOP
+                EXIT
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.regs.flags.z = z;
@@ -1,12 +1,10 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::cpu::Flag;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::RegisterId::{A, B, C, D, E, H, L};
 
-use crate::common::{assert_flags_eq, build_cartridge};
+use crate::common::{EXIT, assert_flags_eq, build_cartridge};
 
 mod common;
 
@@ -15,10 +13,10 @@ fn test_RLCA() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b10101010, // LD A, value
         0x07, // RLCA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -32,10 +30,10 @@ fn test_RLCA_0() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b00000000, // LD A, value
         0x07, // RLCA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -49,10 +47,10 @@ fn test_RLA() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b00101010, // LD A, value
         0x17, // RLA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.regs[Flag::C] = true;
@@ -68,10 +66,10 @@ fn test_RRCA() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b11101010, // LD A, value
         0x0F, // RRCA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -85,10 +83,10 @@ fn test_RRCA_0() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b00000000, // LD A, value
         0x0F, // RRCA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.run(&mut mmu);
@@ -102,10 +100,10 @@ fn test_RRA() {
     let cartridge = build_cartridge(vec![
         0x3E, 0b00101010, // LD A, value
         0x1F, // RRA
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     cpu.regs[Flag::C] = true;
@@ -132,10 +130,10 @@ macro_rules! rotate_tests {
                 0x26, 0xA5, // LD H, $A5
                 0x2E, 0x96, // LD L, $96
                 0xCB, opcode, // <op> x
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.regs.flags.c = c;
@@ -171,10 +169,10 @@ fn test_RLC_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x06, // RLC (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0F;
@@ -182,8 +180,8 @@ fn test_RLC_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x1E, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.c);
 }
 
 rotate_tests! {
@@ -207,10 +205,10 @@ fn test_RL_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x16, // RL (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0F;
@@ -219,8 +217,8 @@ fn test_RL_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x1F, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.c);
 }
 
 rotate_tests! {
@@ -244,10 +242,10 @@ fn test_RRC_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x0E, // RRC (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0F;
@@ -256,8 +254,8 @@ fn test_RRC_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x87, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(true, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(cpu.regs.flags.c);
 }
 
 rotate_tests! {
@@ -281,10 +279,10 @@ fn test_RR_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x1E, // RR (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0E;
@@ -293,8 +291,8 @@ fn test_RR_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x87, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.c);
 }
 
 rotate_tests! {
@@ -318,10 +316,10 @@ fn test_SLA_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x26, // SLA (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0E;
@@ -330,8 +328,8 @@ fn test_SLA_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x1C, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.c);
 }
 
 rotate_tests! {
@@ -355,10 +353,10 @@ fn test_SRA_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x2E, // SRA (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0E;
@@ -367,8 +365,8 @@ fn test_SRA_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x07, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.c);
 }
 
 rotate_tests! {
@@ -392,10 +390,10 @@ fn test_SRL_HL() {
         0x26, 0xA5, // LD H, $A5
         0x2E, 0x96, // LD L, $96
         0xCB, 0x3E, // SRA (HL)
-        0x10, 0x00, // STOP
+        EXIT,
     ]);
 
-    let mut cpu = cpu::init_cpu();
+    let mut cpu = common::init_cpu();
     let mut mmu = Mmu::new(cartridge);
 
     mmu[0xA596] = 0x0E;
@@ -404,6 +402,6 @@ fn test_SRL_HL() {
     cpu.run(&mut mmu);
 
     assert_eq!(0x07, mmu[0xA596]);
-    assert_eq!(false, cpu.regs.flags.z);
-    assert_eq!(false, cpu.regs.flags.c);
+    assert!(!cpu.regs.flags.z);
+    assert!(!cpu.regs.flags.c);
 }
@@ -0,0 +1,67 @@
+use ruboy::lcd::{Frame, SCREEN_HEIGHT, SCREEN_WIDTH};
+use ruboy::screenshot::{decode_png, encode_png, to_rgb, Palette};
+
+#[test]
+fn test_parse_palette() {
+    assert_eq!(Ok(Palette::Grayscale), "grayscale".parse());
+    assert_eq!(Ok(Palette::ClassicGreen), "green".parse());
+    assert_eq!(Ok(Palette::Custom([[0xFF, 0xEE, 0xDD], [0x12, 0x34, 0x56], [0, 0, 0], [0x0A, 0x0B, 0x0C]])),
+               "FFEEDD,123456,000000,#0a0b0c".parse());
+
+    assert!("FFEEDD,123456,000000".parse::<Palette>().is_err());
+    assert!("FFEEDD,123456,000000,0000000".parse::<Palette>().is_err());
+    assert!("blue".parse::<Palette>().is_err());
+}
+
+#[test]
+fn test_to_rgb() {
+    let rgb = to_rgb(&Frame::blank(), Palette::ClassicGreen);
+
+    assert_eq!(SCREEN_WIDTH * SCREEN_HEIGHT * 3, rgb.len());
+    assert_eq!(&[0x9B, 0xBC, 0x0F], &rgb[..3]);
+}
+
+#[test]
+fn test_encode_png() {
+    let png = encode_png(&Frame::blank(), Palette::Grayscale);
+
+    assert_eq!(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A], &png[..8]);
+    // IHDR: 160x144, 2 bits per pixel, indexed colors
+    assert_eq!(&[0, 0, 0, 13], &png[8..12]);
+    assert_eq!(b"IHDR", &png[12..16]);
+    assert_eq!(&[0, 0, 0, 160, 0, 0, 0, 144, 2, 3, 0, 0, 0], &png[16..29]);
+    // PLTE
+    assert_eq!(&[0, 0, 0, 12], &png[33..37]);
+    assert_eq!(b"PLTE", &png[37..41]);
+    assert_eq!(&[0xFF, 0xFF, 0xFF, 0xAA, 0xAA, 0xAA, 0x55, 0x55, 0x55, 0x00, 0x00, 0x00], &png[41..53]);
+    // IEND and its CRC
+    assert_eq!(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82], &png[png.len() - 12..]);
+
+    // Scanlines and their filter byte, stored in one block, with zlib and chunk framing
+    let data = SCREEN_HEIGHT * (SCREEN_WIDTH / 4 + 1);
+    assert_eq!(8 + 25 + 24 + (12 + 2 + data + 5 + 4) + 12, png.len());
+}
+
+#[test]
+fn test_encode_pixels() {
+    let frame = Frame::from_pixels((0..SCREEN_WIDTH * SCREEN_HEIGHT).map(|i| (i % 4) as u8).collect());
+    let png = encode_png(&frame, Palette::Grayscale);
+
+    // First scanline after the PLTE chunk, IDAT header, zlib header and block header
+    let scanline = 53 + 4 + 8 + 2 + 5;
+    assert_eq!(0, png[scanline]);
+    assert_eq!(&[0x1B; 40], &png[scanline + 1..scanline + 41]);
+}
+
+#[test]
+fn test_decode_png() {
+    let frame = Frame::from_pixels((0..SCREEN_WIDTH * SCREEN_HEIGHT).map(|i| (i * 7 % 4) as u8).collect());
+
+    for palette in [Palette::Grayscale, Palette::ClassicGreen] {
+        assert_eq!(Some(frame.pixels()), decode_png(&encode_png(&frame, palette)).as_ref().map(Frame::pixels));
+    }
+
+    let png = encode_png(&frame, Palette::Grayscale);
+    assert!(decode_png(&png[..png.len() / 2]).is_none());
+    assert!(decode_png(b"not a png").is_none());
+}
@@ -1,13 +1,11 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::Register16Id;
 use ruboy::opcodes::RegisterId;
 use ruboy::opcodes::RegisterId::{A, B, C, D, E, H, HL, L};
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -27,10 +25,10 @@ macro_rules! set_tests {
                 0x26, 0xA5, // LD H, $A5
                 0x2E, 0x96, // LD L, $96
                 0xCB, opcode, // SET b,x
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0xA596] = 0b01010101;
@@ -0,0 +1,273 @@
+//! Runs the SM83 single-step test vectors (https://github.com/SingleStepTests/sm83), one
+//! JSON file per opcode, from `rom/sm83`. Each test gives the initial state, the state after
+//! executing one instruction, and the memory access done on every M-cycle, if any.
+//!
+//! The vectors are not distributed with the repository, so the test is ignored by default.
+//! Run it with `cargo test --test test_sm83 -- --ignored` once they are in `rom/sm83`.
+//! They expect memory to be plain RAM, so the CPU runs on a `FlatBus`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use serde_json::Value;
+
+use ruboy::bus::{Bus, FlatBus};
+use ruboy::cpu;
+use ruboy::cpu::{Cpu, CpuState};
+
+const VECTORS_DIR: &str = "rom/sm83";
+
+#[derive(Debug, PartialEq, Eq)]
+struct State {
+    registers: BTreeMap<&'static str, u16>,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Access {
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+const REGISTERS: [&str; 11] = ["a", "b", "c", "d", "e", "f", "h", "l", "sp", "pc", "ime"];
+
+fn parse_state(json: &Value) -> State {
+    let registers = REGISTERS.iter()
+        .map(|&name| (name, json[name].as_u64().expect("Missing register") as u16))
+        .collect();
+
+    let ram = json["ram"].as_array().expect("Missing RAM")
+        .iter()
+        .map(|entry| (entry[0].as_u64().unwrap() as u16, entry[1].as_u64().unwrap() as u8))
+        .collect();
+
+    State { registers, ram }
+}
+
+/// Memory access of every M-cycle, `None` for internal cycles.
+fn parse_cycles(json: &Value) -> Vec<Option<Access>> {
+    json.as_array().expect("Missing cycles")
+        .iter()
+        .map(|cycle| {
+            let kind = cycle.get(2).and_then(Value::as_str).unwrap_or("---");
+            let address = || cycle[0].as_u64().expect("Missing address") as u16;
+            let value = || cycle[1].as_u64().expect("Missing value") as u8;
+
+            if kind.starts_with('r') {
+                Some(Access::Read(address(), value()))
+            } else if kind.contains('w') {
+                Some(Access::Write(address(), value()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Flat memory recording the M-cycles of the CPU, and the access done in each of them.
+/// The CPU ticks the bus at the start of every M-cycle, before its access.
+struct RecordingBus {
+    memory: FlatBus,
+    cycles: Vec<Option<Access>>,
+}
+
+impl RecordingBus {
+    fn record(&mut self, access: Access) {
+        match self.cycles.last_mut() {
+            Some(cycle @ None) => *cycle = Some(access),
+            _ => panic!("{:?} outside of an M-cycle, or in the same M-cycle as another access", access),
+        }
+    }
+}
+
+impl Bus for RecordingBus {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.memory.read(address);
+        self.record(Access::Read(address, value));
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.memory.write(address, value);
+        self.record(Access::Write(address, value));
+    }
+
+    fn tick(&mut self, cycles: u8) {
+        self.memory.tick(cycles);
+        self.cycles.extend((0..cycles / 4).map(|_| None));
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        self.memory.peek(address)
+    }
+
+    fn poke(&mut self, address: u16, value: u8) {
+        self.memory.poke(address, value);
+    }
+}
+
+fn load_state(cpu: &mut Cpu, bus: &mut RecordingBus, state: &State) {
+    let r = &state.registers;
+
+    cpu.set_state(CpuState {
+        a: r["a"] as u8,
+        f: r["f"] as u8,
+        b: r["b"] as u8,
+        c: r["c"] as u8,
+        d: r["d"] as u8,
+        e: r["e"] as u8,
+        h: r["h"] as u8,
+        l: r["l"] as u8,
+        sp: r["sp"],
+        pc: r["pc"],
+        ime: r["ime"] != 0,
+        ..CpuState::default()
+    });
+
+    for &(address, value) in &state.ram {
+        bus.poke(address, value);
+    }
+}
+
+fn save_state(cpu: &Cpu, bus: &RecordingBus, expected: &State) -> State {
+    let state = cpu.state();
+    let values = [state.a, state.b, state.c, state.d, state.e, state.f, state.h, state.l];
+
+    let mut registers: BTreeMap<&'static str, u16> = REGISTERS[..8].iter()
+        .zip(values)
+        .map(|(&name, value)| (name, value as u16))
+        .collect();
+    registers.insert("sp", state.sp);
+    registers.insert("pc", state.pc);
+    registers.insert("ime", state.ime as u16);
+
+    let ram = expected.ram.iter().map(|&(address, _)| (address, bus.peek(address))).collect();
+
+    State { registers, ram }
+}
+
+/// Runs a single test, returning a description of the first difference found.
+fn run_test(test: &Value) -> Result<(), String> {
+    let initial = parse_state(&test["initial"]);
+    let expected = parse_state(&test["final"]);
+    let expected_cycles = parse_cycles(&test["cycles"]);
+
+    let mut cpu = cpu::init_cpu();
+    let mut bus = RecordingBus { memory: FlatBus::new(), cycles: Vec::new() };
+    load_state(&mut cpu, &mut bus, &initial);
+
+    panic::catch_unwind(AssertUnwindSafe(|| cpu.step(&mut bus)))
+        .map_err(|_| "emulator error".to_owned())?;
+
+    let state = save_state(&cpu, &bus, &expected);
+    if state != expected {
+        return Err(format!("expected {:?}, got {:?}", expected, state));
+    }
+
+    if bus.cycles != expected_cycles {
+        return Err(format!("expected cycles {:?}, got {:?}", expected_cycles, bus.cycles));
+    }
+
+    Ok(())
+}
+
+/// Runs every test of a file, returning the number of tests run and the first failure.
+fn run_file(json: &str) -> (usize, Option<String>) {
+    let tests: Value = serde_json::from_str(json).expect("Invalid test vectors");
+    let mut count = 0;
+
+    for test in tests.as_array().expect("Expected an array of tests") {
+        count += 1;
+        if let Err(message) = run_test(test) {
+            return (count, Some(format!("{}: {}", test["name"], message)));
+        }
+    }
+
+    (count, None)
+}
+
+#[test]
+#[ignore = "needs the SM83 test vectors in rom/sm83"]
+fn test_sm83_vectors() {
+    let dir = Path::new(VECTORS_DIR);
+    assert!(dir.is_dir(), "{} not found", dir.display());
+
+    let mut files: Vec<_> = fs::read_dir(dir).expect("Couldn't list test vectors")
+        .map(|entry| entry.expect("Couldn't list test vectors").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+
+    let mut failures = Vec::new();
+    for path in &files {
+        let json = fs::read_to_string(path).expect("Couldn't read test vectors");
+        let (count, failure) = run_file(&json);
+
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        match failure {
+            Some(message) => failures.push(format!("{}: {}", name, message)),
+            None => println!("{}: {} tests passed", name, count),
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} opcodes failed:\n{}\n", failures.len(), files.len(), failures.join("\n"));
+}
+
+/// Checks the harness itself against hand-written vectors.
+#[test]
+fn test_sm83_harness() {
+    let vectors = r#"[
+        {
+            "name": "80 0000",
+            "initial": { "pc": 49152, "sp": 65534, "a": 15, "b": 1, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0,
+                         "ime": 0, "ram": [[49152, 128]] },
+            "final": { "pc": 49153, "sp": 65534, "a": 16, "b": 1, "c": 0, "d": 0, "e": 0, "f": 32, "h": 0, "l": 0,
+                       "ime": 0, "ram": [[49152, 128]] },
+            "cycles": [[49152, 128, "r-m"]]
+        },
+        {
+            "name": "77 0000",
+            "initial": { "pc": 49152, "sp": 65534, "a": 66, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 208, "l": 0,
+                         "ime": 0, "ram": [[49152, 119], [53248, 0]] },
+            "final": { "pc": 49153, "sp": 65534, "a": 66, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 208, "l": 0,
+                       "ime": 0, "ram": [[49152, 119], [53248, 66]] },
+            "cycles": [[49152, 119, "r-m"], [53248, 66, "-wm"]]
+        },
+        {
+            "name": "77 0001",
+            "initial": { "pc": 49152, "sp": 65534, "a": 66, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 224, "l": 0,
+                         "ime": 0, "ram": [[49152, 119], [57344, 0]] },
+            "final": { "pc": 49153, "sp": 65534, "a": 66, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 224, "l": 0,
+                       "ime": 0, "ram": [[49152, 119], [57344, 66]] },
+            "cycles": [[49152, 119, "r-m"], [57344, 66, "-wm"]]
+        },
+        {
+            "name": "03 0000",
+            "initial": { "pc": 49152, "sp": 65534, "a": 0, "b": 0, "c": 255, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0,
+                         "ime": 1, "ram": [[49152, 3]] },
+            "final": { "pc": 49153, "sp": 65534, "a": 0, "b": 1, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0,
+                       "ime": 1, "ram": [[49152, 3]] },
+            "cycles": [[49152, 3, "r-m"], null]
+        }
+    ]"#;
+
+    assert_eq!((4, None), run_file(vectors));
+
+    let wrong_flags = vectors.replace(r#""f": 32"#, r#""f": 0"#);
+    let (_, failure) = run_file(&wrong_flags);
+    assert!(failure.unwrap().starts_with("\"80 0000\": expected"));
+
+    let wrong_accesses = vectors.replace(r#"[53248, 66, "-wm"]"#, r#"[53248, 67, "-wm"]"#);
+    let (_, failure) = run_file(&wrong_accesses);
+    assert!(failure.unwrap().starts_with("\"77 0000\": expected cycles"));
+
+    let wrong_ime = vectors.replacen(r#""ime": 0"#, r#""ime": 1"#, 1);
+    let (_, failure) = run_file(&wrong_ime);
+    assert!(failure.unwrap().starts_with("\"80 0000\": expected"));
+
+    let missing_internal_cycle = vectors.replace(r#"[[49152, 3, "r-m"], null]"#, r#"[[49152, 3, "r-m"]]"#);
+    let (_, failure) = run_file(&missing_internal_cycle);
+    assert!(failure.unwrap().starts_with("\"03 0000\": expected cycles"));
+}
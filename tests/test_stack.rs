@@ -1,12 +1,10 @@
 #![allow(non_snake_case)]
 
-use ruboy;
-use ruboy::cpu;
 use ruboy::memory::Mmu;
 use ruboy::opcodes::{Register16Id::{BC, DE, HL}};
 use ruboy::opcodes::Register16Id::AF;
 
-use crate::common::build_cartridge;
+use crate::common::{EXIT, build_cartridge};
 
 mod common;
 
@@ -23,10 +21,10 @@ macro_rules! push_tests {
                 0x11, 0x44, 0x33, // LD DE, $3344
                 0x21, 0x66, 0x55, // LD HL, $5566
                 opcode, // PUSH xx
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             cpu.run(&mut mmu);
@@ -57,10 +55,10 @@ macro_rules! pop_tests {
 
             let cartridge = build_cartridge(vec![
                 opcode, // POP xx
-                0x10, 0x00, // STOP
+                EXIT,
             ]);
 
-            let mut cpu = cpu::init_cpu();
+            let mut cpu = common::init_cpu();
             let mut mmu = Mmu::new(cartridge);
 
             mmu[0xFFFD] = 0x22;
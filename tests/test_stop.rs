@@ -0,0 +1,103 @@
+use ruboy::cpu::StepResult;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+const P1: u16 = 0xFF00;
+const DIV: u16 = 0xFF04;
+const KEY1: u16 = 0xFF4D;
+
+#[test]
+fn test_stop_low_power_mode() {
+    let cartridge = build_cartridge(vec![
+        0x10, 0x00, // STOP
+        0x3E, 0x42, // LD A, $42
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    mmu.tick(255);
+    mmu.tick(255);
+
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert!(cpu.is_stopped());
+    assert_eq!(0x0102, cpu.regs.pc);
+
+    // Nothing runs, not even the timer, until a button is pressed
+    for _ in 0..1000 {
+        assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    }
+    assert!(cpu.is_stopped());
+    assert_eq!(0x0102, cpu.regs.pc);
+    assert_eq!(0x00, mmu[DIV]);
+
+    mmu[P1] = 0xDE;
+    cpu.run(&mut mmu);
+
+    assert!(!cpu.is_stopped());
+    assert_eq!(0x42, cpu.regs[A]);
+    assert_eq!(0x0104, cpu.regs.pc);
+}
+
+#[test]
+fn test_stop_speed_switch() {
+    let cartridge = build_cartridge(vec![
+        0x10, 0x00, // STOP
+        0x10, 0x00, // STOP
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+    mmu.tick(255);
+
+    // Switches to double speed instead of stopping
+    mmu[KEY1] = 0x01;
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert!(!cpu.is_stopped());
+    assert_eq!(0x80, mmu[KEY1]);
+    assert_eq!(0x00, mmu[DIV]);
+
+    mmu[KEY1] = 0x81;
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert!(!cpu.is_stopped());
+    assert_eq!(0x00, mmu[KEY1]);
+}
+
+#[test]
+fn test_exit_opcode() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    assert_eq!(StepResult::Continue, cpu.step(&mut mmu));
+    assert_eq!(StepResult::Stopped, cpu.step(&mut mmu));
+    assert_eq!(StepResult::Stopped, cpu.step(&mut mmu));
+
+    // The exit opcode isn't executed
+    assert_eq!(0x0101, cpu.regs.pc);
+    assert_eq!(4, cpu.cycles());
+}
+
+#[test]
+fn test_jr_loop() {
+    let cartridge = build_cartridge(vec![
+        0x28, 0xFE, // JR Z, -2
+        0x18, 0xFE, // JR -2
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    // Z is clear after init_cpu: the first one doesn't loop
+    cpu.run(&mut mmu);
+    assert_eq!(0x0104, cpu.regs.pc);
+}
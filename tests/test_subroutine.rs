@@ -0,0 +1,110 @@
+use ruboy::cpu::{IllegalOpcodePolicy, StepResult};
+use ruboy::gameboy::GameBoy;
+use ruboy::memory::Mmu;
+use ruboy::subroutine::{self, CallError, MemoryChange};
+
+use crate::common::build_cartridge;
+
+mod common;
+
+/// Increments the byte at HL, then sets B through a nested call
+fn increment_program() -> Vec<u8> {
+    let mut program = vec![
+        0x7E, // LD A, (HL)
+        0x3C, // INC A
+        0x77, // LD (HL), A
+        0xCD, 0x10, 0x01, // CALL $0110
+        0xC9, // RET
+    ];
+    program.resize(0x10, 0x00);
+    program.extend([
+        0x06, 0x42, // LD B, $42
+        0xC9, // RET
+    ]);
+    program
+}
+
+#[test]
+fn test_call() {
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(increment_program()));
+
+    let mut state = cpu.state();
+    state.set_hl(0xC000);
+    cpu.set_state(state);
+    mmu[0xC000] = 0x41;
+
+    let call = subroutine::call(&mut cpu, &mut mmu, 0x0100, 1000).unwrap();
+
+    assert_eq!(0x42, call.state.a);
+    assert_eq!(0x42, call.state.b);
+    assert_eq!(0xFFFE, call.state.sp);
+    assert_eq!(subroutine::RETURN_ADDRESS, call.state.pc);
+    assert_eq!(84, call.cycles);
+
+    let change = |address, before, after| MemoryChange { address, before, after };
+    let ram: Vec<MemoryChange> = call.memory.iter().copied().filter(|c| c.address < 0xFF00).collect();
+    assert_eq!(vec![change(0xC000, 0x41, 0x42), change(0xE000, 0x41, 0x42)], ram); // Echo RAM
+
+    // Return addresses left on the stack
+    for expected in [change(0xFFFA, 0x00, 0x06), change(0xFFFB, 0x00, 0x01),
+                     change(0xFFFC, 0x00, 0xFF), change(0xFFFD, 0x00, 0xFF)] {
+        assert!(call.memory.contains(&expected), "{:?}", expected);
+    }
+}
+
+#[test]
+fn test_call_twice() {
+    let mut gameboy = GameBoy::builder(build_cartridge(increment_program())).build().unwrap();
+    gameboy.mmu_mut()[0xC000] = 0x10;
+
+    let mut state = gameboy.cpu().state();
+    state.set_hl(0xC000);
+    gameboy.cpu_mut().set_state(state);
+
+    gameboy.call(0x0100, 1000).unwrap();
+    let call = gameboy.call(0x0100, 1000).unwrap();
+
+    assert_eq!(0x12, call.state.a);
+    assert_eq!(0x12, gameboy.mmu()[0xC000]);
+}
+
+#[test]
+fn test_cycle_limit() {
+    let mut gameboy = GameBoy::builder(build_cartridge(vec![
+        0x18, 0xFE, // JR -2
+    ])).build().unwrap();
+
+    match gameboy.call(0x0100, 1000) {
+        Err(CallError::CycleLimit(state)) => assert_eq!(0x0100, state.pc),
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_interrupted() {
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(vec![
+        0x00, // NOP
+        0xDD, // Illegal opcode
+    ]));
+    cpu.set_illegal_opcode_policy(IllegalOpcodePolicy::Error);
+
+    let error = subroutine::call(&mut cpu, &mut mmu, 0x0100, 1000).unwrap_err();
+
+    assert_eq!(CallError::Interrupted(StepResult::IllegalOpcode { pc: 0x0101, opcode: 0xDD }, cpu.state()), error);
+    assert_eq!("Illegal opcode DD at PC=0101", error.to_string());
+}
+
+#[test]
+fn test_locked() {
+    let mut gameboy = GameBoy::builder(build_cartridge(vec![
+        0x00, // NOP
+        0xDD, // Illegal opcode
+    ])).build().unwrap();
+
+    let error = gameboy.call(0x0100, 1000).unwrap_err();
+
+    assert_eq!(CallError::Locked { pc: 0x0101, opcode: 0xDD }, error);
+    assert_eq!("CPU locked up on illegal opcode DD at PC=0101", error.to_string());
+}
@@ -0,0 +1,159 @@
+use ruboy::cartridge::Cartridge;
+use ruboy::cpu;
+use ruboy::memory::Mmu;
+use ruboy::opcodes::RegisterId::A;
+
+use crate::common::build_cartridge;
+
+mod common;
+
+const DIV: u16 = 0xFF04;
+const TIMA: u16 = 0xFF05;
+const TMA: u16 = 0xFF06;
+const TAC: u16 = 0xFF07;
+const IF: u16 = 0xFF0F;
+
+fn mmu() -> Mmu {
+    Mmu::new(Cartridge { content: vec![0; 0x8000] })
+}
+
+#[test]
+fn test_div() {
+    let mut mmu = mmu();
+    assert_eq!(0x18, mmu[DIV]);
+
+    mmu.write(DIV, 0x42);
+    assert_eq!(0x00, mmu[DIV]);
+
+    mmu.tick(255);
+    assert_eq!(0x00, mmu[DIV]);
+    mmu.tick(1);
+    assert_eq!(0x01, mmu[DIV]);
+
+    for _ in 0..255 * 4 {
+        mmu.tick(64);
+    }
+    assert_eq!(0x00, mmu[DIV]);
+}
+
+#[test]
+fn test_tima_frequencies() {
+    for (tac, period) in [(0x04, 1024), (0x05, 16), (0x06, 64), (0x07, 256)] {
+        let mut mmu = mmu();
+        mmu.write(TAC, tac);
+        mmu.write(TIMA, 0x00);
+        mmu.write(DIV, 0x00);
+
+        for _ in 0..period / 4 - 1 {
+            mmu.tick(4);
+        }
+        assert_eq!(0x00, mmu[TIMA], "TAC={:02X}", tac);
+        mmu.tick(4);
+        assert_eq!(0x01, mmu[TIMA], "TAC={:02X}", tac);
+    }
+}
+
+#[test]
+fn test_tima_disabled() {
+    let mut mmu = mmu();
+    mmu.write(TAC, 0x01);
+    mmu.write(TIMA, 0x00);
+
+    for _ in 0..1024 {
+        mmu.tick(4);
+    }
+    assert_eq!(0x00, mmu[TIMA]);
+}
+
+#[test]
+fn test_tima_overflow() {
+    let mut mmu = mmu();
+    mmu.write(TAC, 0x05);
+    mmu.write(TMA, 0xAB);
+    mmu.write(TIMA, 0xFF);
+    mmu.write(IF, 0x00);
+    mmu.write(DIV, 0x00);
+
+    mmu.tick(16);
+    assert_eq!(0xAB, mmu[TIMA]);
+    assert_eq!(0x04, mmu[IF]);
+}
+
+#[test]
+fn test_div_reset_falling_edge() {
+    let mut mmu = mmu();
+    mmu.write(TAC, 0x05);
+    mmu.write(DIV, 0x00);
+    mmu.write(TIMA, 0x00);
+
+    // Bit 3 of the counter is set, resetting it counts as a falling edge
+    mmu.tick(8);
+    mmu.write(DIV, 0x00);
+    assert_eq!(0x01, mmu[TIMA]);
+
+    // Bit 3 is clear, nothing happens
+    mmu.tick(4);
+    mmu.write(DIV, 0x00);
+    assert_eq!(0x01, mmu[TIMA]);
+}
+
+/// Resets DIV, waits `nops` NOPs, then reads TIMA, which increments every 16 cycles.
+fn tima_after_reset(nops: usize) -> u8 {
+    let mut program = vec![
+        0x3E, 0x05, // LD A, $05
+        0xE0, 0x07, // LDH ($07), A
+        0xE0, 0x04, // LDH ($04), A
+    ];
+    program.extend(vec![0x00; nops]); // NOP
+    program.extend([0xF0, 0x05]); // LDH A, ($05)
+
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program));
+    for _ in 0..4 + nops {
+        cpu.step(&mut mmu);
+    }
+
+    cpu.regs[A]
+}
+
+#[test]
+fn test_timer_access_timing() {
+    // DIV is written on the last M-cycle of LDH ($04),A, and TIMA is read on the last
+    // M-cycle of LDH A,($05), 12 cycles later: one NOP more brings it to 16
+    let tima = tima_after_reset(0);
+    assert_eq!(tima + 1, tima_after_reset(1));
+    assert_eq!(tima + 1, tima_after_reset(2));
+    assert_eq!(tima + 2, tima_after_reset(5));
+}
+
+/// Resets DIV, waits `nops` NOPs, then returns with the stack on TIMA, and gives the value
+/// popped from it.
+fn tima_popped(nops: usize, ret: u8) -> u8 {
+    let mut program = vec![
+        0x3E, 0x05, // LD A, $05
+        0xE0, 0x07, // LDH ($07), A
+        0x31, 0x05, 0xFF, // LD SP, $FF05
+        0xB7, // OR A, clears Z
+        0xE0, 0x04, // LDH ($04), A
+    ];
+    program.extend(vec![0x00; nops]); // NOP
+    program.push(ret);
+
+    let mut cpu = cpu::init_cpu();
+    let mut mmu = Mmu::new(build_cartridge(program));
+    mmu.write(TMA, 0x00);
+    for _ in 0..6 + nops {
+        cpu.step(&mut mmu);
+    }
+
+    cpu.regs.pc as u8
+}
+
+#[test]
+fn test_ret_cc_access_timing() {
+    // RET NZ checks its condition during an internal M-cycle, so it pops one M-cycle later
+    // than RET
+    for nops in 0..4 {
+        assert_eq!(tima_popped(nops + 1, 0xC9), tima_popped(nops, 0xC0), "{} NOPs", nops);
+    }
+}
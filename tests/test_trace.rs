@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::io::{self, ErrorKind, Write};
+use std::rc::Rc;
+
+use ruboy::memory::Mmu;
+use ruboy::trace::Tracer;
+
+use crate::common::{EXIT, build_cartridge};
+
+mod common;
+
+fn trace_program(program: Vec<u8>) -> Vec<String> {
+    let cartridge = build_cartridge(program);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let sink = lines.clone();
+    cpu.set_tracer(Some(Tracer::with_callback(move |line| sink.borrow_mut().push(line.to_owned()))));
+
+    cpu.run(&mut mmu);
+
+    let lines = lines.borrow().clone();
+    lines
+}
+
+#[test]
+fn test_trace_format() {
+    let lines = trace_program(vec![
+        0x3E, 0x42, // LD A, $42
+        0x06, 0xFF, // LD B, $FF
+        EXIT,
+    ]);
+
+    assert_eq!(vec![
+        "A:01 F:00 B:FF C:13 D:00 E:C1 H:84 L:03 SP:FFFE PC:0100 PCMEM:3E,42,06,FF",
+        "A:42 F:00 B:FF C:13 D:00 E:C1 H:84 L:03 SP:FFFE PC:0102 PCMEM:06,FF,FD,00",
+        "A:42 F:00 B:FF C:13 D:00 E:C1 H:84 L:03 SP:FFFE PC:0104 PCMEM:FD,00,00,00",
+    ], lines);
+}
+
+#[test]
+fn test_trace_flags() {
+    let lines = trace_program(vec![
+        0xAF, // XOR A
+        0x37, // SCF
+        EXIT,
+    ]);
+
+    assert_eq!(3, lines.len());
+    assert!(lines[1].starts_with("A:00 F:80 "), "{}", lines[1]);
+    assert!(lines[2].starts_with("A:00 F:90 "), "{}", lines[2]);
+}
+
+#[test]
+fn test_trace_disabled() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    let count = Rc::new(RefCell::new(0));
+    let sink = count.clone();
+    let mut tracer = Tracer::with_callback(move |_| *sink.borrow_mut() += 1);
+    tracer.set_enabled(false);
+    cpu.set_tracer(Some(tracer));
+
+    cpu.run(&mut mmu);
+
+    assert_eq!(0, *count.borrow());
+}
+
+/// Fails every write, counting them
+struct BrokenPipe(Rc<RefCell<usize>>);
+
+impl Write for BrokenPipe {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        *self.0.borrow_mut() += 1;
+        Err(io::Error::from(ErrorKind::BrokenPipe))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_trace_write_error() {
+    let cartridge = build_cartridge(vec![
+        0x00, // NOP
+        0x3E, 0x42, // LD A, $42
+        EXIT,
+    ]);
+
+    let mut cpu = common::init_cpu();
+    let mut mmu = Mmu::new(cartridge);
+
+    let writes = Rc::new(RefCell::new(0));
+    cpu.set_tracer(Some(Tracer::to_writer(BrokenPipe(writes.clone()))));
+
+    // The emulation goes on, without tracing
+    cpu.run(&mut mmu);
+
+    assert_eq!(0x0103, cpu.regs.pc);
+    assert_eq!(1, *writes.borrow());
+    let tracer = cpu.take_tracer().unwrap();
+    assert_eq!(ErrorKind::BrokenPipe, tracer.error().unwrap().kind());
+}